@@ -1,14 +1,57 @@
+use std::time::Duration;
+
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::{schedule::{hooks::{self, HookSet}, sleep::SleepPolicy}, sound::{self, cues::{CueSound, SoundSet}}};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppSettings {
-    pub sound_path: Option<String>
+    /// Played for every transition that doesn't have a sound of its own in `sounds`.
+    pub sound_path: Option<String>,
+    #[serde(default, skip_serializing_if = "SoundSet::is_empty")]
+    pub sounds: SoundSet,
+    /// How long before the end of each block to warn that it's ending, latest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Duration>,
+    /// Send a desktop notification whenever a block starts.
+    #[serde(default)]
+    pub notifications: bool,
+    /// External players to fall back on, tried in order. None uses `sound::DEFAULT_PLAYERS`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound_players: Option<Vec<String>>,
+    /// What happens when the computer goes to sleep part way through a block.
+    #[serde(default)]
+    pub on_sleep: SleepPolicy,
+    /// Commands run as the schedule gets to each event.
+    #[serde(default, skip_serializing_if = "HookSet::is_empty")]
+    pub hooks: HookSet,
+    /// How long each hook can run for. None uses `hooks::DEFAULT_TIMEOUT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_timeout: Option<Duration>,
 }
 
 impl AppSettings {
-    pub fn default() -> AppSettings {
-        AppSettings {
-            sound_path: None,
+    pub fn sound_players(&self) -> Vec<String> {
+        match &self.sound_players {
+            Some(players) => players.clone(),
+            None => sound::DEFAULT_PLAYERS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// What's used for the sounds `sounds` doesn't set.
+    pub fn every_transition_sounds(&self) -> SoundSet {
+        match &self.sound_path {
+            Some(path) => SoundSet::all_transitions(CueSound::File(path.clone())),
+            None => SoundSet::default(),
         }
     }
-}
\ No newline at end of file
+
+    /// The sounds a schedule plays, with anything it doesn't set itself taken from these settings.
+    pub fn sounds_for(&self, schedule_sounds: &SoundSet) -> SoundSet {
+        schedule_sounds.or(&self.sounds.or(&self.every_transition_sounds()))
+    }
+
+    pub fn hook_timeout(&self) -> Duration {
+        self.hook_timeout.unwrap_or(hooks::DEFAULT_TIMEOUT)
+    }
+}
@@ -1,5 +1,5 @@
-use std::{io::{self, Write}, process::{Command, Stdio, Child}};
-use crossterm::{event::{self, Event, KeyEvent, KeyEventKind}, cursor, terminal, ExecutableCommand};
+use std::{io::{self, Write}, thread, time::{Duration, Instant}};
+use crossterm::{event::{self, Event, KeyEvent, KeyEventKind}, terminal};
 
 use crate::app::save_load::EXPECT_VALID_UTF8;
 
@@ -8,10 +8,6 @@ pub fn clear() {
     io::stdout().flush().expect(EXPECT_VALID_UTF8);
 }
 
-pub fn clear_line() {
-    io::stdout().execute(terminal::Clear(terminal::ClearType::CurrentLine)).expect(EXPECT_VALID_UTF8);
-}
-
 pub fn get_input() -> String {
     let mut buf = String::new();
     io::stdin().read_line(&mut buf).expect(EXPECT_VALID_UTF8);
@@ -23,10 +19,6 @@ pub fn get_input_trimmed() -> String {
     get_input().trim().to_string()
 }
 
-pub fn move_cursor_to(x: u16, y: u16) {
-    io::stdout().execute(cursor::MoveTo(x, y)).expect(EXPECT_VALID_UTF8);
-}
-
 pub fn wait_for_key_press() -> KeyEvent {
     terminal::enable_raw_mode().expect(EXPECT_VALID_UTF8);
 
@@ -42,6 +34,32 @@ pub fn wait_for_key_press() -> KeyEvent {
     }
 }
 
+/// Waits up to `timeout` for a key press without blocking past it.
+/// Raw mode should already be enabled, otherwise keys will only arrive after the user hits enter.
+pub fn poll_key_press(timeout: Duration) -> Option<KeyEvent> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        match event::poll(remaining) {
+            Ok(true) => {
+                if let Ok(Event::Key(event)) = event::read() {
+                    if event.kind == KeyEventKind::Press {
+                        return Some(event);
+                    }
+                }
+            }
+            Ok(false) => return None,
+            Err(_) => {
+                //No terminal to read from, so just wait the time out
+                thread::sleep(remaining);
+                return None;
+            }
+        }
+    }
+}
+
 pub fn yes_or_no() -> Option<bool> {
     let response = get_input_trimmed();
 
@@ -52,16 +70,14 @@ pub fn yes_or_no() -> Option<bool> {
     }
 }
 
-pub fn flush() {
-    io::stdout().flush().expect(EXPECT_VALID_UTF8);
+/// Rings the terminal's bell, which most terminals play as a short tick or flash.
+pub fn bell() {
+    print!("\x07");
+    flush();
 }
 
-pub fn play_sound(path: &str) -> Result<Child, io::Error> {
-    Command::new("mpg123")
-        .arg(path)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
+pub fn flush() {
+    io::stdout().flush().expect(EXPECT_VALID_UTF8);
 }
 
 pub fn get_input_trimmed_exclude(excludes: &[&str], case_sensitive: bool) -> Result<String, usize> {
@@ -79,5 +95,5 @@ pub fn get_input_trimmed_exclude(excludes: &[&str], case_sensitive: bool) -> Res
         }
     }
 
-    return Ok(input);
+    Ok(input)
 }
\ No newline at end of file
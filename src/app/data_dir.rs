@@ -0,0 +1,190 @@
+use std::{env, ffi::OsString, fs, io, path::{Path, PathBuf}};
+
+use super::save_load::{SCHEDULE_FILE, SETTINGS_FILE};
+
+const APP_DIR: &str = "automato-p";
+/// Where everything used to be kept, relative to wherever the app was launched from.
+const LEGACY_DIR: &str = "./user";
+
+pub const DATA_DIR_ENV: &str = "AUTOMATO_P_DATA_DIR";
+
+/// Where settings (`config`) and schedules (`data`) are kept, and where the daemon's socket goes (`runtime`).
+#[derive(Debug, Clone)]
+pub struct DataDirs {
+    pub config: PathBuf,
+    pub data: PathBuf,
+    pub runtime: PathBuf,
+    /// Where files from older versions might be waiting to be moved from, or None if they shouldn't be looked for.
+    pub legacy: Option<PathBuf>,
+}
+
+impl DataDirs {
+    /// Keeps everything in one directory if `override_dir` (from --data-dir) or $AUTOMATO_P_DATA_DIR is given,
+    /// otherwise follows the XDG base directory spec.
+    pub fn resolve(override_dir: Option<PathBuf>) -> DataDirs {
+        DataDirs::resolve_with(override_dir, |var| env::var_os(var))
+    }
+
+    /// `resolve`, with environment variables looked up by `var`.
+    fn resolve_with(override_dir: Option<PathBuf>, var: impl Fn(&str) -> Option<OsString>) -> DataDirs {
+        let override_dir = override_dir.or_else(|| var(DATA_DIR_ENV).filter(|d| !d.is_empty()).map(PathBuf::from));
+
+        //Whoever picked the directory has their files where they want them already
+        if let Some(dir) = override_dir {
+            return DataDirs { config: dir.clone(), data: dir.clone(), runtime: dir, legacy: None };
+        }
+
+        let home = var("HOME").filter(|h| !h.is_empty()).map(PathBuf::from);
+        let xdg_dir = |name, home, fallback| xdg_dir(var(name), home, fallback);
+
+        match (xdg_dir("XDG_CONFIG_HOME", home.as_deref(), ".config"), xdg_dir("XDG_DATA_HOME", home.as_deref(), ".local/share")) {
+            (Some(config), Some(data)) => DataDirs {
+                //There's no fallback for the runtime directory in the spec, so the data directory will have to do
+                runtime: xdg_dir("XDG_RUNTIME_DIR", None, "").map_or_else(|| data.join(APP_DIR), |runtime| runtime.join(APP_DIR)),
+                config: config.join(APP_DIR),
+                data: data.join(APP_DIR),
+                legacy: Some(PathBuf::from(LEGACY_DIR)),
+            },
+            //Nowhere sensible to put anything, so stick with the old behaviour
+            _ => DataDirs { config: LEGACY_DIR.into(), data: LEGACY_DIR.into(), runtime: LEGACY_DIR.into(), legacy: None },
+        }
+    }
+
+    /// Moves files out of the ./user directory older versions kept next to wherever the app was launched.
+    /// Either every file there is moved or none are, so schedules never end up split from the settings they were
+    /// made with, and nothing is moved if it would overwrite something. Returns the paths of the files that were moved.
+    pub fn migrate_legacy(&self) -> io::Result<Vec<PathBuf>> {
+        let Some(legacy) = self.legacy.as_deref().filter(|legacy| legacy.is_dir()) else {
+            return Ok(Vec::new());
+        };
+
+        let moves: Vec<(PathBuf, PathBuf)> = [(SCHEDULE_FILE, &self.data), (SETTINGS_FILE, &self.config)]
+            .into_iter()
+            .map(|(name, to_dir)| (legacy.join(name), to_dir.join(name)))
+            .filter(|(from, _)| from.is_file())
+            .collect();
+
+        if moves.iter().any(|(_, to)| to.exists() || to.parent().is_some_and(|to_dir| is_same_dir(legacy, to_dir))) {
+            return Ok(Vec::new());
+        }
+
+        //Everything is copied before anything is removed, so a failure part way leaves the old files as they were
+        let mut copied = Vec::new();
+
+        for (from, to) in &moves {
+            let result = to.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::copy(from, to));
+
+            if let Err(e) = result {
+                for to in copied {
+                    fs::remove_file(to).ok();
+                }
+
+                return Err(e);
+            }
+
+            copied.push(to.clone());
+        }
+
+        for (from, _) in &moves {
+            fs::remove_file(from)?;
+        }
+
+        //Only gets rid of the directory if it's empty now
+        fs::remove_dir(legacy).ok();
+
+        Ok(copied)
+    }
+}
+
+/// The XDG spec says relative paths in these variables should be ignored.
+fn xdg_dir(value: Option<OsString>, home: Option<&Path>, fallback: &str) -> Option<PathBuf> {
+    match value.map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => home.map(|h| h.join(fallback)),
+    }
+}
+
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn resolve(override_dir: Option<&str>, vars: &[(&str, &str)]) -> DataDirs {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+
+        DataDirs::resolve_with(override_dir.map(PathBuf::from), |var| vars.get(var).map(OsString::from))
+    }
+
+    #[test]
+    fn directories_should_follow_the_xdg_spec_unless_overridden() {
+        let dirs = resolve(None, &[("HOME", "/home/me"), ("XDG_CONFIG_HOME", "/cfg"), ("XDG_RUNTIME_DIR", "/run/user/1000")]);
+        assert_eq!(dirs.config, Path::new("/cfg/automato-p"));
+        assert_eq!(dirs.data, Path::new("/home/me/.local/share/automato-p"));
+        assert_eq!(dirs.runtime, Path::new("/run/user/1000/automato-p"));
+        assert_eq!(dirs.legacy.as_deref(), Some(Path::new(LEGACY_DIR)));
+
+        let dirs = resolve(None, &[("HOME", "/home/me"), ("XDG_DATA_HOME", "relative/data")]);
+        assert_eq!(dirs.config, Path::new("/home/me/.config/automato-p"));
+        assert_eq!(dirs.data, Path::new("/home/me/.local/share/automato-p"), "relative paths should be ignored");
+        assert_eq!(dirs.runtime, dirs.data);
+
+        let dirs = resolve(None, &[("HOME", "/home/me"), (DATA_DIR_ENV, "/elsewhere")]);
+        assert_eq!((dirs.config, dirs.data, dirs.runtime, dirs.legacy), ("/elsewhere".into(), "/elsewhere".into(), "/elsewhere".into(), None));
+
+        let dirs = resolve(Some("/given"), &[("HOME", "/home/me"), (DATA_DIR_ENV, "/elsewhere")]);
+        assert_eq!(dirs.data, Path::new("/given"), "--data-dir should win over the environment");
+
+        let dirs = resolve(None, &[]);
+        assert_eq!((dirs.config, dirs.data, dirs.legacy), (LEGACY_DIR.into(), LEGACY_DIR.into(), None));
+    }
+
+    /// DataDirs with config and data in separate directories, along with a legacy directory holding `files`.
+    fn legacy_setup(name: &str, files: &[&str]) -> (TestDir, DataDirs) {
+        let root = TestDir::new(&format!("migrate-{name}"));
+        fs::create_dir_all(root.join("user")).unwrap();
+
+        for file in files {
+            fs::write(root.join("user").join(file), *file).unwrap();
+        }
+
+        let dirs = DataDirs { config: root.join("config"), data: root.join("data"), runtime: root.join("data"), legacy: Some(root.join("user")) };
+
+        (root, dirs)
+    }
+
+    #[test]
+    fn legacy_files_should_all_be_moved_together() {
+        let (root, dirs) = legacy_setup("all", &[SCHEDULE_FILE, SETTINGS_FILE]);
+
+        let moved = dirs.migrate_legacy().unwrap();
+        assert_eq!(moved, vec![dirs.data.join(SCHEDULE_FILE), dirs.config.join(SETTINGS_FILE)]);
+        assert_eq!(fs::read_to_string(dirs.config.join(SETTINGS_FILE)).unwrap(), SETTINGS_FILE);
+        assert!(!root.join("user").exists(), "the emptied legacy directory should be removed");
+    }
+
+    #[test]
+    fn legacy_files_should_be_left_alone_if_any_would_overwrite_something() {
+        let (root, dirs) = legacy_setup("conflict", &[SCHEDULE_FILE, SETTINGS_FILE]);
+        fs::create_dir_all(&dirs.config).unwrap();
+        fs::write(dirs.config.join(SETTINGS_FILE), "newer").unwrap();
+
+        assert!(dirs.migrate_legacy().unwrap().is_empty());
+        assert!(root.join("user").join(SCHEDULE_FILE).is_file());
+        assert!(!dirs.data.join(SCHEDULE_FILE).exists(), "the schedules shouldn't be split from their settings");
+        assert_eq!(fs::read_to_string(dirs.config.join(SETTINGS_FILE)).unwrap(), "newer");
+
+        let overridden = DataDirs { legacy: None, ..dirs.clone() };
+        fs::remove_file(dirs.config.join(SETTINGS_FILE)).unwrap();
+        assert!(overridden.migrate_legacy().unwrap().is_empty(), "nothing should be looked for when the directory was picked");
+        assert!(root.join("user").join(SETTINGS_FILE).is_file());
+    }
+}
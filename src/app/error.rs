@@ -1,4 +1,6 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, io, path::PathBuf};
+
+use super::schedule_share::ShareError;
 
 #[derive(Debug)]
 pub struct PlainTextError(pub String);
@@ -9,4 +11,74 @@ impl Display for PlainTextError {
     }
 }
 
-impl Error for PlainTextError {}
\ No newline at end of file
+impl Error for PlainTextError {}
+
+impl From<SaveLoadError> for PlainTextError {
+    fn from(e: SaveLoadError) -> PlainTextError {
+        PlainTextError(e.to_string())
+    }
+}
+
+/// Something went wrong reading or writing one of the user's files.
+#[derive(Debug)]
+pub enum SaveLoadError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// `line` counts from 1, and is None for files that are parsed as a whole (like settings).
+    Parse {
+        path: PathBuf,
+        line: Option<usize>,
+        source: serde_json::Error,
+    },
+    MissingFile(PathBuf),
+    Serialize(serde_json::Error),
+    /// A file of shared schedules couldn't be imported.
+    Import {
+        path: PathBuf,
+        source: ShareError,
+    },
+    Export {
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl SaveLoadError {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> SaveLoadError {
+        let path = path.into();
+
+        match source.kind() {
+            io::ErrorKind::NotFound => SaveLoadError::MissingFile(path),
+            //Text that isn't UTF-8 is as unreadable as text that isn't JSON, so it's dealt with the same way
+            io::ErrorKind::InvalidData => SaveLoadError::Parse { path, line: None, source: serde_json::Error::io(source) },
+            _ => SaveLoadError::Io { path, source },
+        }
+    }
+}
+
+impl Display for SaveLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveLoadError::Io { path, source } => write!(f, "could not access {}: {source}", path.display()),
+            SaveLoadError::Parse { path, line: Some(line), source } => write!(f, "line {line} of {} is invalid: {source}", path.display()),
+            SaveLoadError::Parse { path, line: None, source } => write!(f, "{} is invalid: {source}", path.display()),
+            SaveLoadError::MissingFile(path) => write!(f, "{} does not exist", path.display()),
+            SaveLoadError::Serialize(source) => write!(f, "could not convert to JSON: {source}"),
+            SaveLoadError::Import { path, source } => write!(f, "could not import {}: {source}", path.display()),
+            SaveLoadError::Export { path, reason } => write!(f, "could not export to {}: {reason}", path.display()),
+        }
+    }
+}
+
+impl Error for SaveLoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SaveLoadError::Io { source, .. } => Some(source),
+            SaveLoadError::Parse { source, .. } | SaveLoadError::Serialize(source) => Some(source),
+            SaveLoadError::Import { source, .. } => Some(source),
+            SaveLoadError::MissingFile(_) | SaveLoadError::Export { .. } => None,
+        }
+    }
+}
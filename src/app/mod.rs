@@ -1,8 +1,11 @@
 pub mod app_settings;
 pub mod console;
+pub mod data_dir;
 pub mod error;
 pub mod save_load;
+pub mod schedule_file;
 pub mod schedule_list;
+pub mod schedule_share;
 
 pub const B_FOR_BACK: &str = "Type BACK at any point to return to the previous menu.";
 pub const BACK_CHARACTERS: [&str; 1] = ["BACK"];
@@ -10,46 +13,161 @@ pub const BACK_CHARACTERS: [&str; 1] = ["BACK"];
 pub const EXPECT_VERIFIED: &str = "Value has already been verified to exist";
 
 
-use crate::{prompts, schedule::Schedule};
-use save_load::SaveLoad;
+use std::{path::Path, time::Duration};
+
+use chrono::{DateTime, Local};
+
+use crate::{history::HistoryEntry, prompts, schedule::{checkpoint::{Checkpoint, Checkpointer}, controls::Controls, hooks::HookSet, render::Renderer, runner::{Progress, RunSummary}, sleep::SleepPolicy, Schedule}, sound::cues::SoundSet};
+use save_load::{LoadedHistory, SaveLoad};
 
 use app_settings::AppSettings;
+use data_dir::DataDirs;
+use error::SaveLoadError;
 use schedule_list::ScheduleList;
+use schedule_share::{ConflictChoice, ImportSummary, ShareFormat};
 
 pub struct AppData {
     app_settings: AppSettings,
     schedule_list: ScheduleList,
     save_load: SaveLoad,
+    /// Things that happened while starting up that the user should know about
+    notices: Vec<String>,
+    /// Left behind by a session that was never finished, like when the app was closed part way through.
+    checkpoint: Option<Checkpoint>,
 }
 
 impl AppData {
-    pub fn push_schedule(&mut self, schedule: Schedule) {
-        self.save_load.append_schedule(&schedule);
+    pub fn push_schedule(&mut self, schedule: Schedule) -> Result<(), SaveLoadError> {
+        self.save_load.append_schedule(&schedule)?;
         self.schedule_list.push(schedule);
+        Ok(())
     }
 
-    pub fn insert_schedule(&mut self, index: usize, schedule: Schedule) {
-        self.save_load.insert_schedule(index, &schedule);
+    pub fn insert_schedule(&mut self, index: usize, schedule: Schedule) -> Result<(), SaveLoadError> {
+        self.save_load.insert_schedule(index, &schedule)?;
         self.schedule_list.insert(index, schedule);
+        Ok(())
     }
 
-    pub fn remove_schedule(&mut self, index: usize) {
-        self.save_load.remove_schedule(index);
+    pub fn remove_schedule(&mut self, index: usize) -> Result<(), SaveLoadError> {
+        self.save_load.remove_schedule(index)?;
         self.schedule_list.remove(index);
+        Ok(())
+    }
+
+    pub fn replace_schedule(&mut self, index: usize, replacement: Schedule) -> Result<(), SaveLoadError> {
+        self.save_load.replace_schedule(index, &replacement)?;
+        self.schedule_list.replace(index, replacement);
+        Ok(())
+    }
+
+    pub fn export_schedules(&self, indices: &[usize], path: &Path, format: ShareFormat) -> Result<(), SaveLoadError> {
+        let schedules: Vec<&Schedule> = indices.iter().map(|&i| self.get_schedule(i)).collect();
+
+        self.save_load.export_schedules(path, &schedules, format)
     }
 
-    pub fn replace_schedule(&mut self, index: usize, replacement: Schedule) {
-        self.save_load.replace_schedule(index, &replacement);
-        self.schedule_list.replace(index, replacement)
+    pub fn write_export(&self, path: &Path, contents: &str) -> Result<(), SaveLoadError> {
+        self.save_load.write_export(path, contents)
+    }
+
+    /// Adds every schedule in the file, asking `resolve` what to do whenever one has the same name as a schedule that's already there.
+    /// Renamed schedules are checked again, in case the new name is taken too.
+    pub fn import_schedules(&mut self, path: &Path, format: ShareFormat, mut resolve: impl FnMut(&Schedule) -> ConflictChoice) -> Result<ImportSummary, SaveLoadError> {
+        let mut summary = ImportSummary::default();
+
+        for mut schedule in self.save_load.import_schedules(path, format)? {
+            loop {
+                let Some(existing) = self.schedule_list.position_by_name(&schedule.name) else {
+                    summary.added.push(schedule.name.clone());
+                    self.push_schedule(schedule)?;
+                    break;
+                };
+
+                match resolve(&schedule) {
+                    ConflictChoice::Rename(name) => schedule.name = name,
+                    ConflictChoice::Overwrite => {
+                        summary.overwritten.push(schedule.name.clone());
+                        self.replace_schedule(existing, schedule)?;
+                        break;
+                    }
+                    ConflictChoice::Skip => {
+                        summary.skipped.push(schedule.name);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(summary)
     }
 
     pub fn display_schedule_list(&self) {
         self.schedule_list.display_list();
     }
 
-    pub fn start_schedule(&self, index: usize) {
-        self.schedule_list
-            .start_schedule(index, self.get_sound_path());
+    /// Runs the schedule, then adds how it went to the session history.
+    pub fn start_schedule(&self, index: usize) -> Result<(), SaveLoadError> {
+        self.run_schedule(index, Local::now(), |schedule, settings, checkpointer| schedule.start(settings, None, checkpointer))
+    }
+
+    /// Carries on with the session `checkpoint` was saved from, at `progress` (which may have moved on from the checkpoint's own).
+    pub fn resume_schedule(&self, index: usize, checkpoint: &Checkpoint, progress: &Progress) -> Result<(), SaveLoadError> {
+        self.run_schedule(index, checkpoint.started_at, |schedule, settings, checkpointer| schedule.start(settings, Some(progress), checkpointer))
+    }
+
+    /// Runs the schedule without a terminal, like `start_schedule` does otherwise. It isn't checkpointed, as there's only the
+    /// one checkpoint, and that belongs to runs in the terminal which can be offered to be resumed when the app is next opened.
+    pub fn run_detached(&self, index: usize, controls: &mut impl Controls, renderer: impl Renderer) -> Result<(), SaveLoadError> {
+        let schedule = self.get_schedule(index);
+        let started_at = Local::now();
+
+        let summary = schedule.run_detached(&self.app_settings, controls, renderer);
+
+        let entry = HistoryEntry::new(&schedule.name, started_at, Local::now(), summary);
+        self.save_load.append_history(&entry)
+    }
+
+    /// Checkpoints the run as it goes, so it can be resumed if the app is closed part way through, then adds it to the history.
+    /// A checkpoint that couldn't be saved doesn't stop the run, but is reported once it's over.
+    fn run_schedule(&self, index: usize, started_at: DateTime<Local>, run: impl FnOnce(&Schedule, &AppSettings, Checkpointer) -> RunSummary) -> Result<(), SaveLoadError> {
+        let schedule = self.get_schedule(index);
+        let mut checkpoint_error = None;
+
+        let checkpointer = Checkpointer::new(&schedule.name, started_at, |checkpoint| {
+            let saved = match checkpoint {
+                Some(checkpoint) => self.save_load.save_checkpoint(checkpoint),
+                None => self.save_load.remove_checkpoint(),
+            };
+
+            if let Err(e) = saved {
+                checkpoint_error = Some(e);
+            }
+        });
+
+        let summary = run(schedule, &self.app_settings, checkpointer);
+
+        let entry = HistoryEntry::new(&schedule.name, started_at, Local::now(), summary);
+        self.save_load.append_history(&entry)?;
+
+        checkpoint_error.map_or(Ok(()), Err)
+    }
+
+    /// The checkpoint of an unfinished session, which is only handed out once so it's only offered once.
+    pub fn take_checkpoint(&mut self) -> Option<Checkpoint> {
+        self.checkpoint.take()
+    }
+
+    /// Puts the unfinished session in the history as it ended up, `ended_at`, instead of resuming it.
+    pub fn end_checkpoint(&self, checkpoint: &Checkpoint, summary: RunSummary, ended_at: DateTime<Local>) -> Result<(), SaveLoadError> {
+        let entry = HistoryEntry::new(&checkpoint.schedule_name, checkpoint.started_at, ended_at, summary);
+        self.save_load.append_history(&entry)?;
+
+        self.save_load.remove_checkpoint()
+    }
+
+    pub fn read_history(&self) -> Result<LoadedHistory, SaveLoadError> {
+        self.save_load.read_history()
     }
 
     pub fn num_schedules(&self) -> usize {
@@ -60,27 +178,195 @@ impl AppData {
         self.schedule_list.get(index)
     }
 
+    /// Looks a schedule up by its index in the list, or failing that by its name.
+    pub fn find_schedule(&self, query: &str) -> Option<usize> {
+        match query.parse::<usize>() {
+            Ok(index) if index < self.num_schedules() => Some(index),
+            _ => self.schedule_list.position_by_name(query),
+        }
+    }
+
+    /// Looks a schedule up by exactly the name it was saved under, for names that weren't typed in.
+    pub fn find_schedule_by_name(&self, name: &str) -> Option<usize> {
+        (0..self.num_schedules()).find(|&index| self.get_schedule(index).name == name)
+    }
+
     pub fn get_sound_path(&self) -> Option<&str> {
         self.app_settings.sound_path.as_deref()
     }
 
-    pub fn set_sound_path(&mut self, new_path: Option<String>) {
-        self.app_settings.sound_path = new_path;
-        self.save_load.save_settings(&self.app_settings);
+    pub fn set_sound_path(&mut self, new_path: Option<String>) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.sound_path = new_path)
+    }
+
+    pub fn sounds(&self) -> &SoundSet {
+        &self.app_settings.sounds
+    }
+
+    /// What the app's sounds fall back on, like the sound played for every transition.
+    pub fn sound_defaults(&self) -> SoundSet {
+        self.app_settings.every_transition_sounds()
+    }
+
+    /// What a schedule plays for anything it doesn't set itself.
+    pub fn schedule_sound_defaults(&self) -> SoundSet {
+        self.app_settings.sounds_for(&SoundSet::default())
+    }
+
+    pub fn set_sounds(&mut self, sounds: SoundSet) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.sounds = sounds)
+    }
+
+    /// How long before the end of each block to warn that it's ending, for schedules that don't have their own warnings.
+    pub fn warnings(&self) -> &[Duration] {
+        &self.app_settings.warnings
+    }
+
+    pub fn set_warnings(&mut self, warnings: Vec<Duration>) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.warnings = warnings)
+    }
+
+    /// The external players sounds fall back on, in the order they're tried.
+    pub fn sound_players(&self) -> Vec<String> {
+        self.app_settings.sound_players()
+    }
+
+    /// None goes back to the default players.
+    pub fn set_sound_players(&mut self, players: Option<Vec<String>>) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.sound_players = players)
+    }
+
+    pub fn on_sleep(&self) -> SleepPolicy {
+        self.app_settings.on_sleep
+    }
+
+    pub fn set_on_sleep(&mut self, policy: SleepPolicy) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.on_sleep = policy)
+    }
+
+    pub fn hooks(&self) -> &HookSet {
+        &self.app_settings.hooks
+    }
+
+    pub fn set_hooks(&mut self, hooks: HookSet) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.hooks = hooks)
+    }
+
+    /// How long each hook can run for before it's stopped.
+    pub fn hook_timeout(&self) -> Duration {
+        self.app_settings.hook_timeout()
+    }
+
+    pub fn set_hook_timeout(&mut self, timeout: Option<Duration>) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.hook_timeout = timeout)
+    }
+
+    pub fn notifications_enabled(&self) -> bool {
+        self.app_settings.notifications
+    }
+
+    pub fn set_notifications(&mut self, enabled: bool) -> Result<(), SaveLoadError> {
+        self.update_settings(|settings| settings.notifications = enabled)
+    }
+
+    /// Saves the settings with `edit` made to them, only keeping the change if they could be saved.
+    fn update_settings(&mut self, edit: impl FnOnce(&mut AppSettings)) -> Result<(), SaveLoadError> {
+        let mut new_settings = self.app_settings.clone();
+        edit(&mut new_settings);
+
+        self.save_load.save_settings(&new_settings)?;
+        self.app_settings = new_settings;
+        Ok(())
+    }
+
+    pub fn take_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.notices)
     }
 }
 
-pub fn startup() -> AppData {
-    let save_load = SaveLoad::new();
+/// Loads everything the app needs. Files that can't be read are set aside (and explained in the notices) instead of failing,
+/// so only problems with reaching the files themselves are errors.
+pub fn startup(dirs: &DataDirs) -> Result<AppData, SaveLoadError> {
+    let mut notices = Vec::new();
+
+    match dirs.migrate_legacy() {
+        Ok(moved) => {
+            for path in moved {
+                notices.push(format!("Moved your old {} from ./user to {}", path.file_name().expect(EXPECT_VERIFIED).to_string_lossy(), path.display()));
+            }
+        }
+        Err(e) => notices.push(format!("Could not move your old files out of ./user: {e}")),
+    }
+
+    let save_load = SaveLoad::new(dirs);
+
+    for path in save_load.init_files()? {
+        notices.push(format!("{} was missing, so it was restored from its backup", path.display()));
+    }
+
+    let mut loaded = save_load.read_schedules()?;
+
+    if loaded.schedules.is_empty() && !loaded.bad_lines.is_empty() {
+        //Nothing could be read at all, which is more likely a mangled file than a few bad edits
+        if let Some(mut backup) = save_load.read_schedule_backup()? {
+            if !backup.schedules.is_empty() {
+                backup.bad_lines.splice(0..0, std::mem::take(&mut loaded.bad_lines));
+                loaded = backup;
+
+                notices.push(String::from("None of your schedules could be read, so they were restored from the backup taken before your last change"));
+            }
+        }
+    }
+
+    if loaded.needs_cleanup() {
+        let quarantine = save_load.quarantine_schedules(&loaded)?;
+
+        for bad in &loaded.bad_lines {
+            notices.push(format!("Skipped a schedule that couldn't be read: {}", bad.error));
+        }
+
+        if !loaded.bad_lines.is_empty() {
+            notices.push(format!("The skipped lines were moved to {}", quarantine.display()));
+        }
+    }
+
+    let app_settings = match save_load.read_settings() {
+        Ok(settings) => settings,
+        Err(e @ SaveLoadError::Parse { .. }) => match save_load.read_settings_backup() {
+            Ok(settings) => {
+                let quarantine = save_load.restore_settings_backup()?;
+                notices.push(format!("Restored your settings from their backup, as {e}. The broken file was moved to {}", quarantine.display()));
 
-    let schedule_list = ScheduleList::from(save_load.read_schedules());
-    let app_settings = save_load.read_settings();
+                settings
+            }
+            Err(_) => {
+                let quarantine = save_load.quarantine_settings()?;
+                notices.push(format!("Reset your settings, as {e}. The old file was moved to {}", quarantine.display()));
+
+                AppSettings::default()
+            }
+        },
+        Err(e) => return Err(e),
+    };
 
-    AppData {
+    let checkpoint = match save_load.read_checkpoint() {
+        Ok(checkpoint) => checkpoint,
+        Err(e @ SaveLoadError::Parse { .. }) => {
+            save_load.remove_checkpoint()?;
+            notices.push(format!("Your last session can't be resumed, as {e}"));
+
+            None
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(AppData {
         save_load,
-        schedule_list,
+        schedule_list: ScheduleList::from(loaded.schedules),
         app_settings,
-    }
+        notices,
+        checkpoint,
+    })
 }
 
 pub fn run(app_data: &mut AppData) -> bool {
@@ -95,27 +381,31 @@ pub fn run(app_data: &mut AppData) -> bool {
         println!("1: Create a new schedule");
         println!("2: Modify a pre-existing schedule");
         println!("3: Change app settings");
-        println!("4: Exit app");
+        println!("4: View session history");
+        println!("5: View statistics");
+        println!("6: Exit app");
 
         let input = {
             let res = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false);
 
             match res {
                 Ok(r) => r,
-                Err(_) => "4".to_string(), //If they type 'B' at the main menu, treat it as exiting the app
+                Err(_) => "6".to_string(), //If they type 'B' at the main menu, treat it as exiting the app
             }
         };
 
         console::clear();
 
         match input.parse::<u8>() {
-            Ok(i @ 0..=4) => {
+            Ok(i @ 0..=6) => {
                 match i {
                     0 => prompts::start_schedule::start(app_data),
                     1 => prompts::create_schedule::start(app_data),
                     2 => prompts::modify_schedule::start(app_data),
                     3 => prompts::modify_app::start(app_data),
-                    4 => return false,
+                    4 => prompts::view_history::start(app_data),
+                    5 => prompts::view_stats::start(app_data),
+                    6 => return false,
                     _ => unreachable!()
                 }
 
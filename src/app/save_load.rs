@@ -1,16 +1,20 @@
 use std::{fs::{OpenOptions, File, self}, io::{self, BufRead, BufWriter, BufReader, Lines, Write}, path::{PathBuf, Path}};
-use crate::schedule::Schedule;
+use crate::{history::HistoryEntry, schedule::{checkpoint::Checkpoint, Schedule}};
 
-use super::app_settings::AppSettings;
+use super::{app_settings::AppSettings, data_dir::DataDirs, error::SaveLoadError, schedule_file, schedule_share::{self, ShareFormat}};
 
-const SCHEDULE_PATH: &str = "./user/schedules.txt";
-const SETTINGS_PATH: &str = "./user/settings.json";
+pub const SCHEDULE_FILE: &str = "schedules.txt";
+pub const SETTINGS_FILE: &str = "settings.json";
+pub const HISTORY_FILE: &str = "history.txt";
+/// Only there while a schedule is running, or if the app was closed before it finished.
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+/// Anything that can't be read gets moved to a file with this extension added, so it isn't lost but doesn't stop the app from starting.
+pub const QUARANTINE_EXTENSION: &str = "bad";
+/// The version of each file from before it was last saved is kept with this extension added, in case the latest one gets mangled.
+pub const BACKUP_EXTENSION: &str = "bak";
+const TEMP_EXTENSION: &str = "tmp";
 
 pub const EXPECT_VALID_UTF8: &str = "Line should contain valid UTF-8";
-const EXPECT_FILE: &str = "File should exist, as it is created at the beginning of the program's start";
-const EXPECT_WRITE: &str = "File should be able to be written to";
-const EXPECT_VALID_JSON: &str = "Schedule file should contain valid JSON";
-const EXPECT_VALID_TO_JSON: &str = "Value should be convertible to JSON";
 
 fn read_lines_from_file<P: AsRef<Path>>(path: P) -> Result<Lines<BufReader<File>>, io::Error> {
     let file = OpenOptions::new().read(true).open(path)?;
@@ -31,21 +35,76 @@ fn read_from_file<P: AsRef<Path>>(path: P) -> Result<String, io::Error> {
 }
 
 fn append_to_file<P: AsRef<Path>>(path: P, contents: &str) -> Result<(), io::Error> {
-    let file = OpenOptions::new().append(true).open(path)?;
+    let file = OpenOptions::new().append(true).create(true).open(path)?;
     let mut writer = BufWriter::new(file);
 
     writer.write_all(contents.as_bytes())?;
-    Ok(())
+    writer.flush()
 }
 
+/// Writes to a temporary file next to `path` and renames it over the top, so a crash or a full disk part way through
+/// leaves the old contents intact. Whatever was there before is kept as a backup.
 fn write_to_file<P: AsRef<Path>>(path: P, contents: &str) -> Result<(), io::Error> {
-    let file = OpenOptions::new().write(true).truncate(true).open(path)?;
-    let mut writer = BufWriter::new(file);
+    write_atomically(path.as_ref(), contents, true)
+}
+
+/// For replacing a file that's known to be broken, which shouldn't push a good backup out of the way.
+fn write_to_file_without_backup<P: AsRef<Path>>(path: P, contents: &str) -> Result<(), io::Error> {
+    write_atomically(path.as_ref(), contents, false)
+}
+
+fn write_atomically(path: &Path, contents: &str, keep_backup: bool) -> Result<(), io::Error> {
+    let temp = with_extension_added(path, TEMP_EXTENSION);
+
+    let written = File::create(&temp).and_then(|mut file| {
+        //Set before anything's written, so a file only its owner can read never has its contents in one anyone can
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+
+    if let Err(e) = written {
+        fs::remove_file(&temp).ok();
+        return Err(e);
+    }
+
+    if keep_backup && path.is_file() {
+        //Copied rather than renamed so there's never a moment without the file in place
+        let backup = with_extension_added(path, BACKUP_EXTENSION);
+        let backup_temp = with_extension_added(&backup, TEMP_EXTENSION);
+
+        fs::copy(path, &backup_temp)?;
+        fs::rename(&backup_temp, &backup)?;
+    }
+
+    fs::rename(&temp, path)?;
+
+    //Makes sure the rename itself survives a crash, which not every platform allows
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        File::open(parent).and_then(|dir| dir.sync_all()).ok();
+    }
 
-    writer.write_all(contents.as_bytes())?;
     Ok(())
 }
 
+/// Puts the backup of `path` back in its place, if there is one with anything in it.
+fn restore_from_backup(path: &Path) -> Result<bool, io::Error> {
+    let backup = with_extension_added(path, BACKUP_EXTENSION);
+
+    if !matches!(is_file_empty(&backup), Ok(false)) {
+        return Ok(false);
+    }
+
+    let temp = with_extension_added(path, TEMP_EXTENSION);
+    fs::copy(&backup, &temp)?;
+    fs::rename(&temp, path)?;
+
+    Ok(true)
+}
+
 fn is_file_empty<P: AsRef<Path>>(path: P) -> Result<bool, io::Error> {
     let lines = read_lines_from_file(path);
 
@@ -56,129 +115,559 @@ fn is_file_empty<P: AsRef<Path>>(path: P) -> Result<bool, io::Error> {
     }
 }
 
+fn with_extension_added(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+
+    path.with_file_name(name)
+}
+
+fn read_schedules_from(path: &Path) -> Result<LoadedSchedules, SaveLoadError> {
+    let file = OpenOptions::new().read(true).open(path).map_err(|e| SaveLoadError::io(path, e))?;
+    //Split by hand rather than with `lines`, which would give up on the whole file at the first line that isn't UTF-8
+    let lines = BufReader::new(file).split(b'\n');
+
+    let mut loaded = LoadedSchedules {
+        schedules: Vec::new(),
+        bad_lines: Vec::new(),
+        has_blank_lines: false,
+        has_old_versions: false,
+        from_backup: false,
+    };
+
+    for (line, line_number) in lines.zip(1..) {
+        let mut line = line.map_err(|e| SaveLoadError::io(path, e))?;
+
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        let line = match String::from_utf8(line) {
+            Ok(line) => line,
+            Err(e) => {
+                let source = serde_json::Error::io(io::Error::new(io::ErrorKind::InvalidData, e.utf8_error()));
+
+                //The bytes that aren't UTF-8 are replaced, as the quarantine file is written as text
+                loaded.bad_lines.push(BadLine {
+                    contents: String::from_utf8_lossy(e.as_bytes()).into_owned(),
+                    error: SaveLoadError::Parse { path: path.to_path_buf(), line: Some(line_number), source },
+                });
+
+                continue;
+            }
+        };
+
+        if line.trim().is_empty() {
+            loaded.has_blank_lines = true;
+            continue;
+        }
+
+        match schedule_file::parse_line(&line) {
+            Ok(parsed) => {
+                loaded.has_old_versions |= parsed.version != schedule_file::CURRENT_VERSION;
+                loaded.schedules.push(parsed.schedule);
+            }
+            Err(source) => loaded.bad_lines.push(BadLine {
+                error: SaveLoadError::Parse { path: path.to_path_buf(), line: Some(line_number), source },
+                contents: line,
+            }),
+        }
+    }
+
+    Ok(loaded)
+}
+
+fn schedule_to_line(schedule: &Schedule) -> Result<String, SaveLoadError> {
+    schedule_file::to_line(schedule).map_err(SaveLoadError::Serialize)
+}
+
+/// A line of the schedule file that couldn't be turned into a schedule.
+pub struct BadLine {
+    pub contents: String,
+    pub error: SaveLoadError,
+}
+
+pub struct LoadedSchedules {
+    pub schedules: Vec<Schedule>,
+    pub bad_lines: Vec<BadLine>,
+    /// Blank lines aren't errors, but they still throw off the line numbers that schedules are saved at.
+    has_blank_lines: bool,
+    /// Lines written in an older version of the format are rewritten in the current one.
+    has_old_versions: bool,
+    /// Set when `schedules` came from the backup instead of the schedule file.
+    pub from_backup: bool,
+}
+
+impl LoadedSchedules {
+    /// Whether the schedule file needs rewriting before schedules can be saved to it by index.
+    pub fn needs_cleanup(&self) -> bool {
+        !self.bad_lines.is_empty() || self.has_blank_lines || self.has_old_versions || self.from_backup
+    }
+}
+
+pub struct LoadedHistory {
+    pub entries: Vec<HistoryEntry>,
+    pub bad_lines: Vec<BadLine>,
+}
+
 pub struct SaveLoad {
     schedule_path: PathBuf,
     settings_path: PathBuf,
+    history_path: PathBuf,
+    checkpoint_path: PathBuf,
 }
 
 impl SaveLoad {
-    pub fn new() -> SaveLoad {
-        if let Ok(true) | Err(_) = is_file_empty(&SCHEDULE_PATH) {
-            SaveLoad::init_schedule_file(&SCHEDULE_PATH);
+    pub fn new(dirs: &DataDirs) -> SaveLoad {
+        SaveLoad {
+            schedule_path: dirs.data.join(SCHEDULE_FILE),
+            settings_path: dirs.config.join(SETTINGS_FILE),
+            history_path: dirs.data.join(HISTORY_FILE),
+            checkpoint_path: dirs.data.join(CHECKPOINT_FILE),
         }
+    }
 
-        if let Ok(true) | Err(_) = is_file_empty(&SETTINGS_PATH) {
-            SaveLoad::init_settings_file(&SETTINGS_PATH);
+    /// Makes sure the schedule and settings files exist, putting their backups back in place if they've gone missing
+    /// and starting from the defaults otherwise. Returns the paths that were restored from backups.
+    /// An empty schedule file is left alone, as that's what deleting every schedule leaves behind.
+    pub fn init_files(&self) -> Result<Vec<PathBuf>, SaveLoadError> {
+        let mut restored = Vec::new();
+
+        for (path, init) in [
+            (&self.schedule_path, SaveLoad::init_schedule_file as fn(&Path) -> Result<(), SaveLoadError>),
+            (&self.settings_path, SaveLoad::init_settings_file),
+        ] {
+            //Anything other than it plainly not being there (like not being allowed to look) is left for reading it to report
+            if path.try_exists().map_err(|e| SaveLoadError::io(path, e))? {
+                continue;
+            }
+
+            SaveLoad::init_parent_dir(path)?;
+
+            if restore_from_backup(path).map_err(|e| SaveLoadError::io(path, e))? {
+                restored.push(path.clone());
+            } else {
+                init(path)?;
+            }
         }
 
-        SaveLoad {
-            schedule_path: SCHEDULE_PATH.into(),
-            settings_path: SETTINGS_PATH.into(),
-        } 
+        Ok(restored)
+    }
+
+    /// Reads every schedule it can. Lines that can't be read are handed back rather than failing the whole file.
+    pub fn read_schedules(&self) -> Result<LoadedSchedules, SaveLoadError> {
+        read_schedules_from(&self.schedule_path)
     }
 
-    pub fn read_schedules(&self) -> Vec<Schedule> {
-        read_lines_from_file(&self.schedule_path).expect(EXPECT_FILE)
-            .map(|line| serde_json::from_str(&line.expect(EXPECT_VALID_UTF8)).expect(EXPECT_VALID_JSON))
-            .collect()
+    /// Reads the schedules as they were before the last change, or None if there's no backup.
+    pub fn read_schedule_backup(&self) -> Result<Option<LoadedSchedules>, SaveLoadError> {
+        let backup = with_extension_added(&self.schedule_path, BACKUP_EXTENSION);
+
+        match read_schedules_from(&backup) {
+            Ok(mut loaded) => {
+                loaded.from_backup = true;
+                Ok(Some(loaded))
+            }
+            Err(SaveLoadError::MissingFile(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves the bad lines out of the schedule file and into its quarantine file, returning the quarantine file's path.
+    /// Everything else is written back in the current format.
+    pub fn quarantine_schedules(&self, loaded: &LoadedSchedules) -> Result<PathBuf, SaveLoadError> {
+        let quarantine = with_extension_added(&self.schedule_path, QUARANTINE_EXTENSION);
+
+        if !loaded.bad_lines.is_empty() {
+            let bad: String = loaded.bad_lines.iter().map(|bad| bad.contents.clone() + "\n").collect();
+            append_to_file(&quarantine, &bad).map_err(|e| SaveLoadError::io(&quarantine, e))?;
+        }
+
+        let mut buf = String::new();
+
+        for schedule in &loaded.schedules {
+            buf.push_str(&schedule_to_line(schedule)?);
+        }
+
+        write_to_file_without_backup(&self.schedule_path, &buf).map_err(|e| SaveLoadError::io(&self.schedule_path, e))?;
+
+        Ok(quarantine)
     }
 
-    pub fn append_schedule(&self, schedule: &Schedule) {
-        let mut json = serde_json::to_string(schedule).expect(EXPECT_VALID_TO_JSON);
-        json.push('\n');
+    pub fn append_schedule(&self, schedule: &Schedule) -> Result<(), SaveLoadError> {
+        let mut contents = read_from_file(&self.schedule_path).map_err(|e| SaveLoadError::io(&self.schedule_path, e))?;
+        contents.push_str(&schedule_to_line(schedule)?);
 
-        append_to_file(&self.schedule_path, &json).expect(EXPECT_VALID_UTF8);
+        write_to_file(&self.schedule_path, &contents).map_err(|e| SaveLoadError::io(&self.schedule_path, e))
     }
 
-    pub fn insert_schedule(&self, index: usize, schedule: &Schedule) {
-        let lines = read_lines_from_file(&self.schedule_path).expect(EXPECT_FILE);
+    fn rewrite_schedule_lines(&self, mut edit: impl FnMut(usize, String, &mut String)) -> Result<(), SaveLoadError> {
+        let lines = read_lines_from_file(&self.schedule_path).map_err(|e| SaveLoadError::io(&self.schedule_path, e))?;
         let mut buf = String::new();
-        
-        let to_string = serde_json::to_string(schedule).expect(EXPECT_VALID_TO_JSON) + "\n";
 
-        lines.map(|line| line.expect(EXPECT_VALID_UTF8) + "\n")
-        .enumerate()
-        .for_each(|(i, line)| {
+        for (i, line) in lines.enumerate() {
+            let line = line.map_err(|e| SaveLoadError::io(&self.schedule_path, e))? + "\n";
+            edit(i, line, &mut buf);
+        }
+
+        write_to_file(&self.schedule_path, &buf).map_err(|e| SaveLoadError::io(&self.schedule_path, e))
+    }
+
+    pub fn insert_schedule(&self, index: usize, schedule: &Schedule) -> Result<(), SaveLoadError> {
+        let to_string = schedule_to_line(schedule)?;
+
+        self.rewrite_schedule_lines(|i, line, buf| {
             if i == index {
                 buf.push_str(&to_string);
             } 
             
             buf.push_str(&line);
-        });
+        })
+    }
 
-        let mut file = OpenOptions::new().write(true).truncate(true).open(&self.schedule_path).unwrap();
-        file.write_all(buf.as_bytes()).expect(EXPECT_VALID_UTF8);
+    pub fn remove_schedule(&self, index: usize) -> Result<(), SaveLoadError> {
+        self.rewrite_schedule_lines(|i, line, buf| {
+            if i != index {
+                buf.push_str(&line);
+            }
+        })
     }
 
-    pub fn remove_schedule(&self, index: usize) {
-        let lines = read_lines_from_file(&self.schedule_path).expect(EXPECT_FILE);
-        let mut buf = String::new();
+    pub fn replace_schedule(&self, index: usize, replacement: &Schedule) -> Result<(), SaveLoadError> {
+        let to_string = schedule_to_line(replacement)?;
+
+        self.rewrite_schedule_lines(|i, line, buf| {
+            if i == index {
+                buf.push_str(&to_string);
+            } else {
+                buf.push_str(&line);
+            }
+        })
+    }
 
-        lines
-            .map(|line| line.expect(EXPECT_VALID_UTF8) + "\n")
-            .enumerate()
-            .for_each(|(i, line)| {
-                if i != index {
-                    buf.push_str(&line);
-                }
-            });
+    /// Writes schedules to a standalone file that can be handed to someone else and imported.
+    pub fn export_schedules(&self, path: &Path, schedules: &[&Schedule], format: ShareFormat) -> Result<(), SaveLoadError> {
+        let contents = schedule_share::to_string(schedules, format)
+            .map_err(|reason| SaveLoadError::Export { path: path.to_path_buf(), reason })?;
 
-        let mut file = OpenOptions::new().write(true).truncate(true).open(&self.schedule_path).expect(EXPECT_FILE);
-        file.write_all(buf.as_bytes()).expect(EXPECT_WRITE);
+        self.write_export(path, &contents)
     }
 
-    pub fn replace_schedule(&self, index: usize, replacement: &Schedule) {
-        let lines = read_lines_from_file(&self.schedule_path).expect(EXPECT_FILE);
-        let mut buf = String::new();
+    /// Writes a file for use outside the app, like an exported calendar. There's no backup, as it isn't the app's to keep.
+    pub fn write_export(&self, path: &Path, contents: &str) -> Result<(), SaveLoadError> {
+        write_to_file_without_backup(path, contents).map_err(|e| SaveLoadError::io(path, e))
+    }
 
-        let to_string = serde_json::to_string(replacement).expect(EXPECT_VALID_TO_JSON) + "\n";
+    /// Reads the schedules in a file made by `export_schedules`. They aren't added to the schedule list, as what to do
+    /// about names that are already taken is up to the caller.
+    pub fn import_schedules(&self, path: &Path, format: ShareFormat) -> Result<Vec<Schedule>, SaveLoadError> {
+        let contents = read_from_file(path).map_err(|e| SaveLoadError::io(path, e))?;
+
+        schedule_share::from_str(&contents, format).map_err(|source| SaveLoadError::Import { path: path.to_path_buf(), source })
+    }
 
-        lines
-            .map(|line| line.expect(EXPECT_VALID_UTF8) + "\n")
-            .enumerate()
-            .for_each(|(i, line)| {
-                if i == index {
-                    buf.push_str(&to_string);
-                } else {
-                    buf.push_str(&line);
-                }
-            });
+    pub fn read_settings(&self) -> Result<AppSettings, SaveLoadError> {
+        let contents = read_from_file(&self.settings_path).map_err(|e| SaveLoadError::io(&self.settings_path, e))?;
 
-        let mut file = OpenOptions::new().write(true).truncate(true).open(&self.schedule_path).expect(EXPECT_FILE);
-        file.write_all(buf.as_bytes()).expect(EXPECT_VALID_UTF8);
+        serde_json::from_str(&contents).map_err(|source| SaveLoadError::Parse { path: self.settings_path.clone(), line: None, source })
     }
 
-    pub fn read_settings(&self) -> AppSettings {
-        let contents = read_from_file(&self.settings_path).expect(EXPECT_FILE);
-        serde_json::from_str(&contents).expect(EXPECT_VALID_JSON)
+    /// Reads the settings as they were before they were last saved.
+    pub fn read_settings_backup(&self) -> Result<AppSettings, SaveLoadError> {
+        let backup = with_extension_added(&self.settings_path, BACKUP_EXTENSION);
+        let contents = read_from_file(&backup).map_err(|e| SaveLoadError::io(&backup, e))?;
+
+        serde_json::from_str(&contents).map_err(|source| SaveLoadError::Parse { path: backup, line: None, source })
+    }
+
+    /// Moves the settings file out of the way and starts again from the default settings, returning where the old file went.
+    pub fn quarantine_settings(&self) -> Result<PathBuf, SaveLoadError> {
+        let quarantine = self.move_settings_to_quarantine()?;
+        SaveLoad::init_settings_file(&self.settings_path)?;
+
+        Ok(quarantine)
+    }
+
+    /// Moves the settings file out of the way and puts its backup in its place, returning where the old file went.
+    /// The backup is copied rather than saved again, so it's still there if the restored settings turn out to be wrong.
+    pub fn restore_settings_backup(&self) -> Result<PathBuf, SaveLoadError> {
+        let backup = with_extension_added(&self.settings_path, BACKUP_EXTENSION);
+
+        if !matches!(is_file_empty(&backup), Ok(false)) {
+            return Err(SaveLoadError::MissingFile(backup));
+        }
+
+        let quarantine = self.move_settings_to_quarantine()?;
+        restore_from_backup(&self.settings_path).map_err(|e| SaveLoadError::io(&self.settings_path, e))?;
+
+        Ok(quarantine)
+    }
+
+    fn move_settings_to_quarantine(&self) -> Result<PathBuf, SaveLoadError> {
+        let quarantine = with_extension_added(&self.settings_path, QUARANTINE_EXTENSION);
+
+        fs::rename(&self.settings_path, &quarantine).map_err(|e| SaveLoadError::io(&quarantine, e))?;
+
+        Ok(quarantine)
+    }
+
+    pub fn save_settings(&self, new_settings: &AppSettings) -> Result<(), SaveLoadError> {
+        let json = serde_json::to_string_pretty(&new_settings).map_err(SaveLoadError::Serialize)? + "\n";
+
+        write_to_file(&self.settings_path, &json).map_err(|e| SaveLoadError::io(&self.settings_path, e))
+    }
+
+    /// History is only ever added to, so a line is appended rather than rewriting the whole file.
+    pub fn append_history(&self, entry: &HistoryEntry) -> Result<(), SaveLoadError> {
+        let json = serde_json::to_string(entry).map_err(SaveLoadError::Serialize)? + "\n";
+
+        append_to_file(&self.history_path, &json).map_err(|e| SaveLoadError::io(&self.history_path, e))
+    }
+
+    /// Reads every history entry it can, oldest first. Having no history file just means there's no history yet.
+    pub fn read_history(&self) -> Result<LoadedHistory, SaveLoadError> {
+        let mut loaded = LoadedHistory {
+            entries: Vec::new(),
+            bad_lines: Vec::new(),
+        };
+
+        let lines = match read_lines_from_file(&self.history_path) {
+            Ok(lines) => lines,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(loaded),
+            Err(e) => return Err(SaveLoadError::io(&self.history_path, e)),
+        };
+
+        for (line, line_number) in lines.zip(1..) {
+            let line = line.map_err(|e| SaveLoadError::io(&self.history_path, e))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(entry) => loaded.entries.push(entry),
+                Err(source) => loaded.bad_lines.push(BadLine {
+                    error: SaveLoadError::Parse { path: self.history_path.clone(), line: Some(line_number), source },
+                    contents: line,
+                }),
+            }
+        }
+
+        Ok(loaded)
     }
 
-    pub fn save_settings(&self, new_settings: &AppSettings) {
-        let json = serde_json::to_string_pretty(&new_settings).expect(EXPECT_VALID_TO_JSON) + "\n";
-        write_to_file(&self.settings_path, &json).expect(EXPECT_FILE);
+    /// It's saved every few seconds and only ever replaced, so there's no backup.
+    pub fn save_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), SaveLoadError> {
+        let json = serde_json::to_string(checkpoint).map_err(SaveLoadError::Serialize)? + "\n";
+
+        write_to_file_without_backup(&self.checkpoint_path, &json).map_err(|e| SaveLoadError::io(&self.checkpoint_path, e))
     }
 
-    fn init_user_dir(parent_dir: &str) {
-        fs::create_dir_all(format!("{parent_dir}/user")).unwrap();
+    /// Reads the checkpoint of a session that was never finished, if there is one.
+    pub fn read_checkpoint(&self) -> Result<Option<Checkpoint>, SaveLoadError> {
+        let contents = match read_from_file(&self.checkpoint_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(SaveLoadError::io(&self.checkpoint_path, e)),
+        };
+
+        serde_json::from_str(&contents).map(Some).map_err(|source| SaveLoadError::Parse { path: self.checkpoint_path.clone(), line: None, source })
     }
 
-    fn init_settings_file(path: &str) {
-        SaveLoad::init_user_dir(".");
-        let file = OpenOptions::new().write(true).create(true).truncate(true).open(path).expect(EXPECT_FILE);
-        let mut writer = BufWriter::new(file);
+    pub fn remove_checkpoint(&self) -> Result<(), SaveLoadError> {
+        match fs::remove_file(&self.checkpoint_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SaveLoadError::io(&self.checkpoint_path, e)),
+            _ => Ok(()),
+        }
+    }
 
-        let json = serde_json::to_string_pretty(&AppSettings::default()).expect(EXPECT_VALID_TO_JSON) + "\n";
+    fn init_parent_dir(path: &Path) -> Result<(), SaveLoadError> {
+        match path.parent() {
+            Some(parent) => fs::create_dir_all(parent).map_err(|e| SaveLoadError::io(parent, e)),
+            None => Ok(()),
+        }
+    }
+
+    fn init_settings_file(path: &Path) -> Result<(), SaveLoadError> {
+        let json = serde_json::to_string_pretty(&AppSettings::default()).map_err(SaveLoadError::Serialize)? + "\n";
             
-        writer.write_all(json.as_bytes()).expect(EXPECT_VALID_UTF8);
+        write_to_file_without_backup(path, &json).map_err(|e| SaveLoadError::io(path, e))
     }
 
-    fn init_schedule_file(path: &str) {
-        SaveLoad::init_user_dir(".");
-        let file = OpenOptions::new().write(true).create(true).truncate(true).open(path).expect(EXPECT_FILE);
-        let mut writer = BufWriter::new(file);
+    fn init_schedule_file(path: &Path) -> Result<(), SaveLoadError> {
+        let json = schedule_to_line(&Schedule::pomodoro())?;
+
+        write_to_file_without_backup(path, &json).map_err(|e| SaveLoadError::io(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schedule::runner::{Outcome, RunSummary}, test_dir::TestDir};
+
+    /// A SaveLoad with every file in a fresh directory of its own.
+    fn save_load(name: &str) -> (TestDir, SaveLoad) {
+        let dir = TestDir::new(&format!("save-load-{name}"));
+        let dirs = DataDirs { config: dir.to_path_buf(), data: dir.to_path_buf(), runtime: dir.to_path_buf(), legacy: None };
+
+        (dir, SaveLoad::new(&dirs))
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn saving_should_keep_the_previous_version_as_a_backup() {
+        let (dir, _) = save_load("write");
+        let path = dir.join("file");
+        let backup = with_extension_added(&path, BACKUP_EXTENSION);
+
+        write_to_file(&path, "one\n").unwrap();
+        assert!(!backup.exists(), "there was nothing to back up");
+
+        write_to_file(&path, "two\n").unwrap();
+        assert_eq!(read(&path), "two\n");
+        assert_eq!(read(&backup), "one\n");
+
+        write_to_file_without_backup(&path, "three\n").unwrap();
+        assert_eq!(read(&path), "three\n");
+        assert_eq!(read(&backup), "one\n");
+
+        assert!(!with_extension_added(&path, TEMP_EXTENSION).exists());
+        assert!(!with_extension_added(&backup, TEMP_EXTENSION).exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn saving_should_keep_the_files_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (dir, _) = save_load("permissions");
+        let path = dir.join("file");
+
+        write_to_file(&path, "one\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        write_to_file(&path, "two\n").unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn missing_files_should_be_restored_from_their_backups() {
+        let (_dir, save_load) = save_load("restore");
+
+        assert!(save_load.init_files().unwrap().is_empty(), "there were no backups to restore");
+        assert_eq!(save_load.read_schedules().unwrap().schedules.len(), 1);
+
+        save_load.append_schedule(&Schedule::pomodoro()).unwrap();
+        fs::remove_file(&save_load.schedule_path).unwrap();
+        fs::remove_file(&save_load.settings_path).unwrap();
+
+        assert_eq!(save_load.init_files().unwrap(), vec![save_load.schedule_path.clone()]);
+        assert_eq!(save_load.read_schedules().unwrap().schedules.len(), 1);
+        assert!(save_load.read_settings().is_ok(), "settings with no backup should start from the defaults");
+    }
+
+    #[test]
+    fn deleting_the_last_schedule_should_not_bring_it_back() {
+        let (_dir, save_load) = save_load("empty");
+
+        save_load.init_files().unwrap();
+        save_load.remove_schedule(0).unwrap();
+
+        assert!(save_load.init_files().unwrap().is_empty());
+        assert!(save_load.read_schedules().unwrap().schedules.is_empty());
+    }
+
+    #[test]
+    fn broken_settings_should_be_replaced_by_their_backup_without_losing_it() {
+        let (_dir, save_load) = save_load("settings");
+        let backup = with_extension_added(&save_load.settings_path, BACKUP_EXTENSION);
+
+        save_load.init_files().unwrap();
+        save_load.save_settings(&AppSettings { notifications: true, ..AppSettings::default() }).unwrap();
+        save_load.save_settings(&AppSettings { notifications: true, ..AppSettings::default() }).unwrap();
+        fs::write(&save_load.settings_path, "{ not json").unwrap();
+        let good_backup = read(&backup);
+
+        assert!(matches!(save_load.read_settings(), Err(SaveLoadError::Parse { .. })));
+        assert!(save_load.read_settings_backup().unwrap().notifications);
+
+        let quarantine = save_load.restore_settings_backup().unwrap();
+        assert_eq!(read(&quarantine), "{ not json");
+        assert!(save_load.read_settings().unwrap().notifications);
+        assert_eq!(read(&backup), good_backup, "restoring shouldn't touch the backup");
+    }
+
+    #[test]
+    fn broken_settings_with_no_backup_should_be_quarantined_and_reset() {
+        let (_dir, save_load) = save_load("quarantine");
+
+        fs::write(&save_load.settings_path, "{ not json").unwrap();
+        assert!(save_load.restore_settings_backup().is_err());
+        assert_eq!(read(&save_load.settings_path), "{ not json", "a failed restore should leave the file where it was");
+
+        let quarantine = save_load.quarantine_settings().unwrap();
+
+        assert_eq!(quarantine, with_extension_added(&save_load.settings_path, QUARANTINE_EXTENSION));
+        assert_eq!(read(&quarantine), "{ not json");
+        assert!(!save_load.read_settings().unwrap().notifications);
+        assert!(!with_extension_added(&save_load.settings_path, BACKUP_EXTENSION).exists());
+    }
+
+    #[test]
+    fn unreadable_schedules_should_be_reported_by_line_and_quarantined() {
+        let (_dir, save_load) = save_load("bad-lines");
+        let good = schedule_to_line(&Schedule::pomodoro()).unwrap();
+
+        let mut contents = good.clone().into_bytes();
+        contents.extend_from_slice(b"{\"version\":6,\"schedule\":{\"name\":\"Broken\"}}\n");
+        contents.extend_from_slice(b"caf\xe9\r\n");
+        fs::write(&save_load.schedule_path, contents).unwrap();
+
+        let loaded = save_load.read_schedules().unwrap();
+        assert_eq!(loaded.schedules.len(), 1);
+        assert!(loaded.needs_cleanup());
+
+        let errors: Vec<String> = loaded.bad_lines.iter().map(|bad| bad.error.to_string()).collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with(&format!("line 2 of {} is invalid", save_load.schedule_path.display())), "{}", errors[0]);
+        assert!(errors[1].starts_with(&format!("line 3 of {} is invalid", save_load.schedule_path.display())), "{}", errors[1]);
+
+        let quarantine = save_load.quarantine_schedules(&loaded).unwrap();
+        assert_eq!(read(&quarantine), "{\"version\":6,\"schedule\":{\"name\":\"Broken\"}}\ncaf\u{FFFD}\n");
+        assert_eq!(read(&save_load.schedule_path), good);
+    }
+
+    #[test]
+    fn settings_that_are_not_utf8_should_count_as_invalid() {
+        let (_dir, save_load) = save_load("utf8");
+
+        fs::write(&save_load.settings_path, b"{\"notifications\": \"caf\xe9\"}\n").unwrap();
+
+        assert!(matches!(save_load.read_settings(), Err(SaveLoadError::Parse { line: None, .. })));
+    }
+
+    #[test]
+    fn history_should_be_appended_and_read_back_in_order() {
+        let (_dir, save_load) = save_load("history");
+        assert!(save_load.read_history().unwrap().entries.is_empty(), "no history file means no history yet");
+
+        let started_at = chrono::Local::now();
+        let summary = |outcome, blocks| RunSummary { outcome, work_blocks_completed: blocks, focused: std::time::Duration::from_secs(60) };
+
+        save_load.append_history(&HistoryEntry::new("Pomodoro", started_at, started_at, summary(Outcome::Finished, 4))).unwrap();
+        append_to_file(&save_load.history_path, "not json\n\n").unwrap();
+        save_load.append_history(&HistoryEntry::new("52/17", started_at, started_at, summary(Outcome::Aborted, 1))).unwrap();
 
-        let json = serde_json::to_string(&Schedule::pomodoro()).expect(EXPECT_VALID_TO_JSON) + "\n";
+        let loaded = save_load.read_history().unwrap();
+        let read: Vec<(&str, Outcome, u32)> = loaded.entries.iter().map(|e| (e.schedule_name.as_str(), e.outcome, e.work_blocks_completed)).collect();
+        assert_eq!(read, [("Pomodoro", Outcome::Finished, 4), ("52/17", Outcome::Aborted, 1)]);
 
-        writer.write_all(json.as_bytes()).expect(EXPECT_VALID_UTF8);
+        assert_eq!(loaded.bad_lines.len(), 1);
+        assert!(matches!(loaded.bad_lines[0].error, SaveLoadError::Parse { line: Some(2), .. }));
     }
 }
@@ -0,0 +1,456 @@
+use serde::{de::Error as _, Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::schedule::Schedule;
+
+/// Every line of the schedule file is an envelope holding the version of the format it was written in, so old lines can be
+/// migrated one version at a time after `Schedule` changes. Each past version keeps a frozen copy of the types it was written with.
+/// - Version 1: a bare `Schedule`, with work/rest durations only.
+/// - Version 2: a bare `Schedule` that can also have a list of phases.
+/// - Version 3: `{"version":3,"schedule":...}`, with the schedule the same as version 2.
+/// - Version 4: the schedule can also have sounds of its own.
+/// - Version 5: the schedule can also have its own warnings before blocks end, and a sound for them.
+/// - Version 6: the schedule can also have its own commands to run when blocks start and sessions end.
+pub const CURRENT_VERSION: u32 = 6;
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    schedule: &'a Schedule,
+}
+
+mod v1 {
+    use std::time::Duration;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub enum RestType {
+        LongRest {
+            blocks_per_long_rest: u32,
+            long_rest_duration: Duration,
+        },
+        Standard,
+    }
+
+    #[derive(Deserialize)]
+    pub enum RepeatType {
+        Infinite,
+        Finite(u32),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Schedule {
+        pub name: String,
+        pub work_duration: Duration,
+        pub rest_duration: Duration,
+        pub repeat_type: RepeatType,
+        pub rest_type: RestType,
+    }
+}
+
+mod v2 {
+    use std::time::Duration;
+    use serde::Deserialize;
+
+    pub use super::v1::{RepeatType, RestType};
+
+    #[derive(Deserialize)]
+    pub enum BlockKind {
+        Work,
+        Rest,
+        LongRest,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Phase {
+        pub label: String,
+        pub duration: Duration,
+        pub kind: BlockKind,
+    }
+
+    #[derive(Deserialize)]
+    pub enum PhaseItem {
+        Phase(Phase),
+        Repeat {
+            times: RepeatType,
+            items: Vec<PhaseItem>,
+        },
+    }
+
+    #[derive(Deserialize)]
+    pub struct Schedule {
+        pub name: String,
+        pub work_duration: Duration,
+        pub rest_duration: Duration,
+        pub repeat_type: RepeatType,
+        pub rest_type: RestType,
+        pub phases: Option<Vec<PhaseItem>>,
+    }
+}
+
+mod v3 {
+    //Only the envelope was new
+    pub use super::v2::*;
+}
+
+mod v4 {
+    use std::time::Duration;
+    use serde::Deserialize;
+
+    pub use super::v3::{BlockKind, PhaseItem, RepeatType, RestType};
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum CueSound {
+        Silent,
+        File(String),
+    }
+
+    #[derive(Default, Deserialize)]
+    pub struct SoundSet {
+        #[serde(default)]
+        pub work_start: Option<CueSound>,
+        #[serde(default)]
+        pub rest_start: Option<CueSound>,
+        #[serde(default)]
+        pub long_rest_start: Option<CueSound>,
+        #[serde(default)]
+        pub complete: Option<CueSound>,
+        #[serde(default)]
+        pub volume: Option<u8>,
+        #[serde(default)]
+        pub repeat: Option<u32>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Schedule {
+        pub name: String,
+        pub work_duration: Duration,
+        pub rest_duration: Duration,
+        pub repeat_type: RepeatType,
+        pub rest_type: RestType,
+        #[serde(default)]
+        pub phases: Option<Vec<PhaseItem>>,
+        #[serde(default)]
+        pub sounds: SoundSet,
+    }
+}
+
+mod v5 {
+    use std::time::Duration;
+    use serde::Deserialize;
+
+    pub use super::v4::{BlockKind, CueSound, PhaseItem, RepeatType, RestType};
+
+    #[derive(Default, Deserialize)]
+    pub struct SoundSet {
+        #[serde(default)]
+        pub work_start: Option<CueSound>,
+        #[serde(default)]
+        pub rest_start: Option<CueSound>,
+        #[serde(default)]
+        pub long_rest_start: Option<CueSound>,
+        #[serde(default)]
+        pub complete: Option<CueSound>,
+        #[serde(default)]
+        pub warning: Option<CueSound>,
+        #[serde(default)]
+        pub volume: Option<u8>,
+        #[serde(default)]
+        pub repeat: Option<u32>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Schedule {
+        pub name: String,
+        pub work_duration: Duration,
+        pub rest_duration: Duration,
+        pub repeat_type: RepeatType,
+        pub rest_type: RestType,
+        #[serde(default)]
+        pub phases: Option<Vec<PhaseItem>>,
+        #[serde(default)]
+        pub sounds: SoundSet,
+        #[serde(default)]
+        pub warnings: Option<Vec<Duration>>,
+    }
+}
+
+fn v1_to_v2(old: v1::Schedule) -> v2::Schedule {
+    v2::Schedule {
+        name: old.name,
+        work_duration: old.work_duration,
+        rest_duration: old.rest_duration,
+        repeat_type: old.repeat_type,
+        rest_type: old.rest_type,
+        phases: None,
+    }
+}
+
+fn v3_to_v4(old: v3::Schedule) -> v4::Schedule {
+    v4::Schedule {
+        name: old.name,
+        work_duration: old.work_duration,
+        rest_duration: old.rest_duration,
+        repeat_type: old.repeat_type,
+        rest_type: old.rest_type,
+        phases: old.phases,
+        sounds: v4::SoundSet::default(),
+    }
+}
+
+fn v4_to_v5(old: v4::Schedule) -> v5::Schedule {
+    v5::Schedule {
+        name: old.name,
+        work_duration: old.work_duration,
+        rest_duration: old.rest_duration,
+        repeat_type: old.repeat_type,
+        rest_type: old.rest_type,
+        phases: old.phases,
+        sounds: v5::SoundSet {
+            work_start: old.sounds.work_start,
+            rest_start: old.sounds.rest_start,
+            long_rest_start: old.sounds.long_rest_start,
+            complete: old.sounds.complete,
+            warning: None,
+            volume: old.sounds.volume,
+            repeat: old.sounds.repeat,
+        },
+        warnings: None,
+    }
+}
+
+fn v5_to_v6(old: v5::Schedule) -> Schedule {
+    use crate::{schedule::{hooks::HookSet, phase::{Phase, PhaseItem}, runner::BlockKind, RepeatType, RestType}, sound::cues::{CueSound, SoundSet}};
+
+    fn repeat_type(old: v5::RepeatType) -> RepeatType {
+        match old {
+            v5::RepeatType::Infinite => RepeatType::Infinite,
+            v5::RepeatType::Finite(blocks) => RepeatType::Finite(blocks),
+        }
+    }
+
+    fn phase_item(old: v5::PhaseItem) -> PhaseItem {
+        match old {
+            v5::PhaseItem::Phase(phase) => PhaseItem::Phase(Phase {
+                label: phase.label,
+                duration: phase.duration,
+                kind: match phase.kind {
+                    v5::BlockKind::Work => BlockKind::Work,
+                    v5::BlockKind::Rest => BlockKind::Rest,
+                    v5::BlockKind::LongRest => BlockKind::LongRest,
+                },
+            }),
+            v5::PhaseItem::Repeat { times, items } => PhaseItem::Repeat {
+                times: repeat_type(times),
+                items: items.into_iter().map(phase_item).collect(),
+            },
+        }
+    }
+
+    fn cue_sound(old: v5::CueSound) -> CueSound {
+        match old {
+            v5::CueSound::Silent => CueSound::Silent,
+            v5::CueSound::File(path) => CueSound::File(path),
+        }
+    }
+
+    Schedule {
+        name: old.name,
+        work_duration: old.work_duration,
+        rest_duration: old.rest_duration,
+        repeat_type: repeat_type(old.repeat_type),
+        rest_type: match old.rest_type {
+            v5::RestType::LongRest { blocks_per_long_rest, long_rest_duration } => RestType::LongRest { blocks_per_long_rest, long_rest_duration },
+            v5::RestType::Standard => RestType::Standard,
+        },
+        phases: old.phases.map(|phases| phases.into_iter().map(phase_item).collect()),
+        sounds: SoundSet {
+            work_start: old.sounds.work_start.map(cue_sound),
+            rest_start: old.sounds.rest_start.map(cue_sound),
+            long_rest_start: old.sounds.long_rest_start.map(cue_sound),
+            complete: old.sounds.complete.map(cue_sound),
+            warning: old.sounds.warning.map(cue_sound),
+            volume: old.sounds.volume,
+            repeat: old.sounds.repeat,
+        },
+        warnings: old.warnings,
+        hooks: HookSet::default(),
+    }
+}
+
+/// A schedule read from a line, along with the version it was written in.
+#[derive(Debug)]
+pub struct ParsedLine {
+    pub schedule: Schedule,
+    pub version: u32,
+}
+
+/// Reads a line written in any version of the format, migrating it up to the current `Schedule`.
+pub fn parse_line(line: &str) -> Result<ParsedLine, serde_json::Error> {
+    let value: Value = serde_json::from_str(line)?;
+
+    let (version, schedule) = match value {
+        Value::Object(mut map) if map.contains_key("version") => {
+            let version = map.remove("version").and_then(|v| v.as_u64()).and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| serde_json::Error::custom("the schedule's version should be a whole number"))?;
+            let schedule = map.remove("schedule").ok_or_else(|| serde_json::Error::missing_field("schedule"))?;
+
+            (version, schedule)
+        }
+        //Before the envelope, version 2 schedules could only be told apart by having phases
+        Value::Object(ref map) if map.contains_key("phases") => (2, value),
+        value => (1, value),
+    };
+
+    let schedule = match version {
+        1 => v5_to_v6(v4_to_v5(v3_to_v4(v1_to_v2(v1::Schedule::deserialize(schedule)?)))),
+        2 | 3 => v5_to_v6(v4_to_v5(v3_to_v4(v3::Schedule::deserialize(schedule)?))),
+        4 => v5_to_v6(v4_to_v5(v4::Schedule::deserialize(schedule)?)),
+        5 => v5_to_v6(v5::Schedule::deserialize(schedule)?),
+        6 => Schedule::deserialize(schedule)?,
+        _ => return Err(serde_json::Error::custom(format!(
+            "the schedule is from version {version} of the schedule format, but only versions up to {CURRENT_VERSION} can be read - is automato-p out of date?"
+        ))),
+    };
+
+    Ok(ParsedLine { schedule, version })
+}
+
+/// Writes a schedule as a line in the current version of the format, including the newline.
+pub fn to_line(schedule: &Schedule) -> Result<String, serde_json::Error> {
+    Ok(serde_json::to_string(&Envelope { version: CURRENT_VERSION, schedule })? + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{schedule::{hooks::HookSet, RepeatType, RestType}, sound::cues::{CueSound, SoundSet}};
+
+    /// Every line of a fixture, which should all be in `version`.
+    fn read_fixture(contents: &str, version: u32) -> Vec<Schedule> {
+        contents.lines()
+            .map(|line| parse_line(line).unwrap_or_else(|e| panic!("could not read {line}: {e}")))
+            .inspect(|parsed| assert_eq!(parsed.version, version))
+            .map(|parsed| parsed.schedule)
+            .collect()
+    }
+
+    fn assert_same_schedules(schedules: &[Schedule], expected: &[Schedule]) {
+        //Schedule doesn't implement PartialEq, and its lines are an exact enough comparison
+        let lines: Vec<String> = schedules.iter().map(|s| to_line(s).unwrap()).collect();
+        let expected: Vec<String> = expected.iter().map(|s| to_line(s).unwrap()).collect();
+
+        assert_eq!(lines, expected);
+    }
+
+    fn classic_schedules() -> Vec<Schedule> {
+        vec![
+            Schedule::pomodoro(),
+            Schedule {
+                name: String::from("52/17"),
+                work_duration: Duration::from_secs(52 * 60),
+                rest_duration: Duration::from_secs(17 * 60),
+                repeat_type: RepeatType::Infinite,
+                rest_type: RestType::Standard,
+                phases: None,
+                sounds: Default::default(),
+                warnings: None,
+                hooks: Default::default(),
+            },
+        ]
+    }
+
+    fn phase_schedule() -> Schedule {
+        let phases = crate::schedule::phase::parse("warm-up 10:00 rest, 3x(work 50:00, break 10:00 rest), lunch 1:00:00 long-rest, *(work 25:00, rest 5:00 rest)").unwrap();
+
+        Schedule::from_phases(String::from("Day"), phases)
+    }
+
+    #[test]
+    fn version_1_fixture_should_migrate() {
+        let schedules = read_fixture(include_str!("../../tests/fixtures/schedules/v1.txt"), 1);
+
+        assert_same_schedules(&schedules, &classic_schedules());
+    }
+
+    #[test]
+    fn version_2_fixture_should_migrate() {
+        let schedules = read_fixture(include_str!("../../tests/fixtures/schedules/v2.txt"), 2);
+
+        assert_same_schedules(&schedules, &[phase_schedule()]);
+    }
+
+    #[test]
+    fn version_3_fixture_should_migrate() {
+        let schedules = read_fixture(include_str!("../../tests/fixtures/schedules/v3.txt"), 3);
+        let mut expected = classic_schedules();
+        expected.push(phase_schedule());
+
+        assert_same_schedules(&schedules, &expected);
+    }
+
+    fn with_sounds(mut schedules: Vec<Schedule>) -> Vec<Schedule> {
+        schedules[1].sounds = SoundSet {
+            work_start: Some(CueSound::File(String::from("/home/me/sounds/gong.ogg"))),
+            complete: Some(CueSound::Silent),
+            volume: Some(70),
+            ..SoundSet::default()
+        };
+
+        schedules
+    }
+
+    #[test]
+    fn version_4_fixture_should_migrate() {
+        let schedules = read_fixture(include_str!("../../tests/fixtures/schedules/v4.txt"), 4);
+        let mut expected = classic_schedules();
+        expected.push(phase_schedule());
+
+        assert_same_schedules(&schedules, &with_sounds(expected));
+    }
+
+    fn with_warnings(schedules: Vec<Schedule>) -> Vec<Schedule> {
+        let mut schedules = with_sounds(schedules);
+        schedules[1].sounds.warning = Some(CueSound::File(String::from("/home/me/sounds/tick.wav")));
+        schedules[2].warnings = Some(vec![Duration::from_secs(60), Duration::from_secs(10)]);
+
+        schedules
+    }
+
+    #[test]
+    fn version_5_fixture_should_migrate() {
+        let schedules = read_fixture(include_str!("../../tests/fixtures/schedules/v5.txt"), 5);
+        let mut expected = classic_schedules();
+        expected.push(phase_schedule());
+
+        assert_same_schedules(&schedules, &with_warnings(expected));
+    }
+
+    #[test]
+    fn version_6_fixture_should_match_what_is_written_now() {
+        let fixture = include_str!("../../tests/fixtures/schedules/v6.txt");
+        let mut expected = classic_schedules();
+        expected.push(phase_schedule());
+        let mut expected = with_warnings(expected);
+        expected[2].hooks = HookSet {
+            work_start: Some(String::from("dnd on")),
+            session_complete: Some(String::from("dnd off")),
+            ..HookSet::default()
+        };
+
+        assert_same_schedules(&read_fixture(fixture, 6), &expected);
+
+        let written: String = expected.iter().map(|s| to_line(s).unwrap()).collect();
+        assert_eq!(written, fixture, "the current format changed, so it needs a new version and fixture");
+    }
+
+    #[test]
+    fn newer_versions_should_be_rejected() {
+        let line = r#"{"version":99,"schedule":{"anything":true}}"#;
+
+        assert!(parse_line(line).unwrap_err().to_string().contains("version 99"));
+    }
+}
@@ -6,22 +6,24 @@ pub struct ScheduleList {
     list: Vec<Schedule>,
 }
 
-impl ScheduleList {
-    pub fn default() -> ScheduleList {
+impl Default for ScheduleList {
+    fn default() -> ScheduleList {
         ScheduleList { list: vec![Schedule::pomodoro()] }
     }
+}
 
+impl ScheduleList {
     pub fn from(schedules: Vec<Schedule>) -> ScheduleList {
         ScheduleList { list: schedules }
     }
 
-    pub fn start_schedule(&self, index: usize, alarm_path: Option<&str>) {
-        self.get(index).start(alarm_path);
-    }
-
     pub fn len(&self) -> usize {
         self.list.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
     
     pub fn display_list(&self) {
         self.list.iter().enumerate().for_each(|(i, sch)| println!("{i}: {sch}"));
@@ -31,6 +33,11 @@ impl ScheduleList {
         self.list.get(index).expect(EXPECT_VERIFIED)
     }
 
+    pub fn position_by_name(&self, name: &str) -> Option<usize> {
+        self.list.iter().position(|sch| sch.name == name)
+            .or_else(|| self.list.iter().position(|sch| sch.name.eq_ignore_ascii_case(name)))
+    }
+
     pub fn push(&mut self, schedule: Schedule) {
         self.list.push(schedule);
     }
@@ -0,0 +1,296 @@
+use std::{path::Path, time::Duration};
+use serde::{Serialize, Deserialize};
+
+use crate::{schedule::{format, hooks::HookSet, phase, RepeatType, RestType, Schedule}, sound::cues::SoundSet};
+
+/// Bumped whenever a change to `SharedFile` would stop older versions of the app from reading it.
+pub const SHARE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareFormat {
+    Json,
+    Toml,
+}
+
+impl ShareFormat {
+    pub fn parse(str: &str) -> Option<ShareFormat> {
+        match str.to_ascii_lowercase().as_ref() {
+            "json" => Some(ShareFormat::Json),
+            "toml" => Some(ShareFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// TOML for .toml files, JSON for anything else.
+    pub fn from_path(path: &Path) -> ShareFormat {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ShareFormat::Toml,
+            _ => ShareFormat::Json,
+        }
+    }
+}
+
+/// A file of schedules made for passing around, so durations are written as HH:MM:SS and phases the way they're typed in.
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedFile {
+    version: u32,
+    schedules: Vec<SharedSchedule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedSchedule {
+    name: String,
+    work: String,
+    rest: String,
+    /// Left out for schedules that repeat until they're quit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blocks: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    long_rest: Option<SharedLongRest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    phases: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedLongRest {
+    every: u32,
+    duration: String,
+}
+
+impl From<&Schedule> for SharedSchedule {
+    fn from(schedule: &Schedule) -> SharedSchedule {
+        SharedSchedule {
+            name: schedule.name.clone(),
+            work: format::dur_to_hhmmss(schedule.work_duration),
+            rest: format::dur_to_hhmmss(schedule.rest_duration),
+            blocks: match schedule.repeat_type {
+                RepeatType::Finite(blocks) => Some(blocks),
+                RepeatType::Infinite => None,
+            },
+            long_rest: match schedule.rest_type {
+                RestType::LongRest { blocks_per_long_rest, long_rest_duration } => Some(SharedLongRest {
+                    every: blocks_per_long_rest,
+                    duration: format::dur_to_hhmmss(long_rest_duration),
+                }),
+                RestType::Standard => None,
+            },
+            phases: schedule.phases.as_deref().map(phase::to_spec),
+        }
+    }
+}
+
+fn parse_dur(value: &str, what: &str) -> Result<Duration, String> {
+    format::try_hhmmss_to_dur(value)
+        .ok_or_else(|| format!("'{value}' could not be converted into an HH:MM:SS duration - {what}"))
+}
+
+impl SharedSchedule {
+    /// Checks everything a schedule made in the app couldn't have got wrong, returning every problem at once.
+    fn into_schedule(self) -> Result<Schedule, Vec<String>> {
+        let mut problems = Vec::new();
+
+        let work_duration = parse_dur(&self.work, "work duration").map_err(|e| problems.push(e)).ok();
+        let rest_duration = parse_dur(&self.rest, "rest duration").map_err(|e| problems.push(e)).ok();
+
+        if work_duration == Some(Duration::ZERO) {
+            problems.push(String::from("the work duration can't be zero"));
+        }
+
+        if rest_duration == Some(Duration::ZERO) {
+            problems.push(String::from("the rest duration can't be zero"));
+        }
+
+        if self.blocks == Some(0) {
+            problems.push(String::from("blocks has to be at least 1, or left out to repeat until quit"));
+        }
+
+        let rest_type = match &self.long_rest {
+            Some(long_rest) => {
+                if long_rest.every == 0 {
+                    problems.push(String::from("long rests have to come every 1 or more blocks"));
+                }
+
+                let long_rest_duration = parse_dur(&long_rest.duration, "long rest duration").map_err(|e| problems.push(e)).ok();
+
+                if long_rest_duration == Some(Duration::ZERO) {
+                    problems.push(String::from("the long rest duration can't be zero"));
+                }
+
+                long_rest_duration
+                    .map(|long_rest_duration| RestType::LongRest { blocks_per_long_rest: long_rest.every, long_rest_duration })
+            }
+            None => Some(RestType::Standard),
+        };
+
+        let phases = match &self.phases {
+            Some(spec) => phase::parse(spec).map(Some).map_err(|e| problems.push(format!("the phases could not be read - {e}"))).ok(),
+            None => Some(None),
+        };
+
+        match (work_duration, rest_duration, rest_type, phases) {
+            (Some(work_duration), Some(rest_duration), Some(rest_type), Some(phases)) if problems.is_empty() => Ok(Schedule {
+                name: self.name,
+                work_duration,
+                rest_duration,
+                repeat_type: self.blocks.map_or(RepeatType::Infinite, RepeatType::Finite),
+                rest_type,
+                phases,
+                //Sound files are somewhere on the exporter's computer, so they aren't shared
+                sounds: SoundSet::default(),
+                warnings: None,
+                //Nobody should end up running someone else's commands just by importing their schedule
+                hooks: HookSet::default(),
+            }),
+            _ => Err(problems),
+        }
+    }
+}
+
+/// What to do with an imported schedule whose name is already taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictChoice {
+    Rename(String),
+    Overwrite,
+    Skip,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+/// Something was wrong with a file being imported.
+#[derive(Debug)]
+pub enum ShareError {
+    /// The file couldn't be read as JSON/TOML at all.
+    Syntax(String),
+    UnsupportedVersion(u32),
+    /// Each schedule that was readable but invalid, with what's wrong with it.
+    InvalidSchedules(Vec<(String, Vec<String>)>),
+}
+
+impl std::fmt::Display for ShareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShareError::Syntax(e) => write!(f, "{e}"),
+            ShareError::UnsupportedVersion(version) => {
+                write!(f, "it's from version {version} of the schedule sharing format, but only versions up to {SHARE_VERSION} can be read - is automato-p out of date?")
+            }
+            ShareError::InvalidSchedules(invalid) => {
+                let described: Vec<String> = invalid.iter().map(|(name, problems)| format!("'{name}': {}", problems.join(", "))).collect();
+                write!(f, "{}", described.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+pub fn to_string(schedules: &[&Schedule], format: ShareFormat) -> Result<String, String> {
+    let file = SharedFile {
+        version: SHARE_VERSION,
+        schedules: schedules.iter().map(|&schedule| SharedSchedule::from(schedule)).collect(),
+    };
+
+    match format {
+        ShareFormat::Json => serde_json::to_string_pretty(&file).map(|json| json + "\n").map_err(|e| e.to_string()),
+        ShareFormat::Toml => toml::to_string_pretty(&file).map_err(|e| e.to_string()),
+    }
+}
+
+/// Reads every schedule in the file. Nothing is returned unless all of them are valid, so a typo doesn't lead to half an import.
+pub fn from_str(contents: &str, format: ShareFormat) -> Result<Vec<Schedule>, ShareError> {
+    let file: SharedFile = match format {
+        ShareFormat::Json => serde_json::from_str(contents).map_err(|e| ShareError::Syntax(e.to_string()))?,
+        ShareFormat::Toml => toml::from_str(contents).map_err(|e| ShareError::Syntax(e.message().to_string()))?,
+    };
+
+    if file.version > SHARE_VERSION {
+        return Err(ShareError::UnsupportedVersion(file.version));
+    }
+
+    let mut schedules = Vec::new();
+    let mut invalid = Vec::new();
+
+    for shared in file.schedules {
+        let name = shared.name.clone();
+
+        match shared.into_schedule() {
+            Ok(schedule) => schedules.push(schedule),
+            Err(problems) => invalid.push((name, problems)),
+        }
+    }
+
+    if invalid.is_empty() {
+        Ok(schedules)
+    } else {
+        Err(ShareError::InvalidSchedules(invalid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules_should_survive_a_round_trip_in_both_formats() {
+        let day = Schedule::from_phases(String::from("Day"), phase::parse("warm-up 10:00 rest, 3x(work 50:00, break 10:00 rest)").unwrap());
+        let pomodoro = Schedule::pomodoro();
+
+        for format in [ShareFormat::Json, ShareFormat::Toml] {
+            let written = to_string(&[&pomodoro, &day], format).unwrap();
+            let read = from_str(&written, format).unwrap();
+
+            let names: Vec<String> = read.iter().map(|s| s.to_string()).collect();
+            assert_eq!(names, vec![pomodoro.to_string(), day.to_string()], "{format:?}:\n{written}");
+        }
+    }
+
+    #[test]
+    fn invalid_durations_should_reject_the_whole_file() {
+        let toml = r#"
+            version = 1
+
+            [[schedules]]
+            name = "Fine"
+            work = "25:00"
+            rest = "5:00"
+
+            [[schedules]]
+            name = "Broken"
+            work = "25 minutes"
+            rest = "5:00"
+            long_rest = { every = 0, duration = "30:00" }
+        "#;
+
+        let Err(ShareError::InvalidSchedules(invalid)) = from_str(toml, ShareFormat::Toml) else {
+            panic!("the broken schedule should have been caught");
+        };
+
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0, "Broken");
+        assert_eq!(invalid[0].1.len(), 2, "{:?}", invalid[0].1);
+    }
+
+    #[test]
+    fn zero_length_phases_should_be_rejected() {
+        let json = r#"{
+            "version": 1,
+            "schedules": [
+                { "name": "Flash", "work": "25:00", "rest": "0", "phases": "*(a 0)" }
+            ]
+        }"#;
+
+        let Err(ShareError::InvalidSchedules(invalid)) = from_str(json, ShareFormat::Json) else {
+            panic!("the zero-length phase should have been caught");
+        };
+
+        assert_eq!(invalid[0].0, "Flash");
+        assert_eq!(invalid[0].1, vec![
+            String::from("the rest duration can't be zero"),
+            String::from("the phases could not be read - '0' has to be longer than zero - a"),
+        ]);
+    }
+}
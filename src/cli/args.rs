@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::app::error::PlainTextError;
+
+/// Command line arguments split into positionals, `--option value`/`--option=value` pairs and `--flag`s that take no value.
+pub struct Args {
+    positional: VecDeque<String>,
+    options: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    /// Any `--flag` that isn't in `options` is an error.
+    pub fn parse(args: &[String], options: &[&str]) -> Result<Args, PlainTextError> {
+        Args::parse_with_flags(args, options, &[])
+    }
+
+    /// Like `parse`, but the names in `flags` are allowed too, without a value.
+    pub fn parse_with_flags(args: &[String], options: &[&str], flags: &[&str]) -> Result<Args, PlainTextError> {
+        let mut parsed = Args {
+            positional: VecDeque::new(),
+            options: HashMap::new(),
+            flags: HashSet::new(),
+        };
+
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                parsed.positional.push_back(arg.clone());
+                continue;
+            };
+
+            let (name, inline_value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+
+            if options.contains(&name) {
+                let value = match inline_value {
+                    Some(v) => v,
+                    None => iter.next()
+                        .ok_or_else(|| PlainTextError(format!("--{name} needs a value")))?
+                        .clone(),
+                };
+
+                parsed.options.insert(name.to_string(), value);
+            } else if flags.contains(&name) {
+                if inline_value.is_some() {
+                    return Err(PlainTextError(format!("--{name} doesn't take a value")));
+                }
+
+                parsed.flags.insert(name.to_string());
+            } else {
+                return Err(PlainTextError(format!("unknown option --{name}")));
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn next_positional(&mut self) -> Option<String> {
+        self.positional.pop_front()
+    }
+
+    pub fn require_positional(&mut self, what: &str) -> Result<String, PlainTextError> {
+        self.next_positional().ok_or_else(|| PlainTextError(format!("missing {what}")))
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// Errors if any positional arguments were left unused.
+    pub fn finish(mut self) -> Result<(), PlainTextError> {
+        match self.next_positional() {
+            Some(extra) => Err(PlainTextError(format!("unexpected argument '{extra}'"))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn positionals_options_and_flags_should_be_told_apart() {
+        let mut parsed = Args::parse_with_flags(&args(&["start", "--format", "json", "Deep work", "--follow", "--every=4"]), &["format", "every"], &["follow"]).unwrap();
+
+        assert_eq!(parsed.option("format"), Some("json"));
+        assert_eq!(parsed.option("every"), Some("4"));
+        assert_eq!(parsed.option("missing"), None);
+        assert!(parsed.flag("follow"));
+        assert!(!parsed.flag("quiet"));
+
+        assert_eq!(parsed.require_positional("command").unwrap(), "start");
+        assert_eq!(parsed.next_positional().as_deref(), Some("Deep work"));
+        assert_eq!(parsed.require_positional("schedule").unwrap_err().0, "missing schedule");
+        assert!(parsed.finish().is_ok());
+    }
+
+    #[test]
+    fn bad_options_should_be_rejected() {
+        let error = |list: &[&str]| Args::parse_with_flags(&args(list), &["format"], &["follow"]).err().map(|e| e.0);
+
+        assert_eq!(error(&["--colour"]).as_deref(), Some("unknown option --colour"));
+        assert_eq!(error(&["--format"]).as_deref(), Some("--format needs a value"));
+        assert_eq!(error(&["--follow=yes"]).as_deref(), Some("--follow doesn't take a value"));
+        assert_eq!(Args::parse(&args(&["--follow"]), &["format"]).err().map(|e| e.0).as_deref(), Some("unknown option --follow"));
+
+        let extra = Args::parse(&args(&["one", "two"]), &[]).unwrap();
+        assert_eq!(extra.finish().unwrap_err().0, "unexpected argument 'one'");
+    }
+}
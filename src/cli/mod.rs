@@ -0,0 +1,703 @@
+pub mod args;
+
+use std::{io::{self, Write}, path::{Path, PathBuf}, thread, time::Duration};
+
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::{
+    app::{self, data_dir::{DataDirs, DATA_DIR_ENV}, error::PlainTextError, schedule_share::{self, ConflictChoice, ShareFormat}, AppData},
+    daemon::{self, client::{self, DaemonError}, protocol::{Request, Status}, status_bar::{self, StatusBar, StatusFormat, Template}},
+    history::stats::{ReportFormat, Stats},
+    schedule::{calendar, format, hooks::HookEvent, phase, plan, sleep::SleepPolicy, warning, RepeatType, RestType, Schedule},
+    sound::{self, cues::{Cue, CueSound, MAX_REPEAT}},
+};
+use args::Args;
+
+pub const USAGE: &str = "\
+Usage: automato-p [--data-dir <DIR>] [COMMAND]
+
+Run without a command to open the interactive menu.
+
+Your schedules and settings are kept in $XDG_DATA_HOME/automato-p and $XDG_CONFIG_HOME/automato-p
+(~/.local/share/automato-p and ~/.config/automato-p by default). Use --data-dir or set
+$AUTOMATO_P_DATA_DIR to keep everything in one directory instead.
+
+Commands:
+  list                                  List your schedules
+  start <NAME|INDEX>                    Start a schedule
+  create --work <HH:MM:SS> --rest <HH:MM:SS> [--name <NAME>] [--blocks <N>] [--long-rest <N>x<HH:MM:SS>]
+                                        Create a schedule. Without --blocks it repeats until you quit,
+                                        --long-rest 4x30:00 gives a 30 minute rest after every 4 work blocks
+  create --phases <PHASES> [--name <NAME>]
+                                        Create a schedule from a list of phases, e.g.
+                                        'warm-up 10:00 rest, 3x(work 50:00, break 10:00 rest), review 15:00'
+                                        (each is work unless marked rest or long-rest, *(...) repeats forever)
+  delete <NAME|INDEX>                   Delete a schedule
+  export [NAME|INDEX] [--output <FILE>] [--format <json|toml>]
+                                        Export schedules (all of them by default) to a file that can be shared
+                                        and imported, or print it. The format follows the file's extension
+  import <FILE> [--format <json|toml>] [--on-conflict <rename|overwrite|skip>]
+                                        Import the schedules in an exported file. Schedules with a name that's
+                                        already taken are skipped unless told otherwise
+  calendar <NAME|INDEX> [--start <[YYYY-MM-DD ]HH:MM>] [--blocks <N>] [--output <FILE>]
+                                        Plan a session as an iCalendar (.ics) file with an event for every block,
+                                        starting now unless given a start time. Infinite schedules need --blocks,
+                                        the number of work blocks to plan for
+  stats [--format <table|json|csv>]     Show statistics about the sessions you've run
+  daemon                                Run schedules in the background, controlled with 'automato-p ctl'
+                                        through a socket in $XDG_RUNTIME_DIR/automato-p
+  ctl <start <NAME|INDEX>|pause|resume|skip|stop|status> [--format <text|json>]
+                                        Control the daemon and show what it's doing afterwards. Scripts can
+                                        also send it JSON lines, like {\"command\":\"start\",\"schedule\":\"Pomodoro\"}
+  status [--format <plain|waybar|tmux>] [--template <TEXT>] [--idle <TEXT>] [--follow]
+                                        Show what the daemon is running on one line for status bars. The template
+                                        can use {schedule}, {block}, {kind}, {state}, {position}, {remaining},
+                                        {remaining_short}, {elapsed} and {elapsed_short} (default '{block} {remaining}'),
+                                        --idle is shown when nothing is running. --follow prints a new line every second
+  settings                              Show the app settings
+  settings set sound-path <PATH|none>   Change the sound played whenever a block starts or the schedule is complete
+  settings set <work-start-sound|rest-start-sound|long-rest-start-sound|complete-sound|warning-sound> <PATH|silent|default>
+                                        Give one transition a sound of its own, or go back to the one above.
+                                        Warnings only make a sound if they're given one
+  settings set volume <0-100|default>   Change how loud sounds are played
+  settings set sound-repeat <N|default> Change how many times each sound plays in a row
+  settings set notifications <on|off>   Send a desktop notification whenever a block starts
+  settings set warnings <TIMES|none>    Warn this long before the end of every block, e.g. '1:00, 10' for a minute
+                                        and ten seconds before (schedules can have their own in the menu)
+  settings set on-sleep <count|pause|ask>
+                                        What happens if the computer sleeps part way through a block: count the
+                                        time as passed, pause the block, or pause it and ask whether to count it
+  settings set <work-start-hook|rest-start-hook|long-rest-start-hook|session-complete-hook|session-abort-hook> <COMMAND|none>
+                                        Run a shell command when a block starts or the session ends (schedules can
+                                        have their own in the menu). It's told about the event through $AUTOMATO_P_EVENT,
+                                        $AUTOMATO_P_SCHEDULE, $AUTOMATO_P_BLOCK, $AUTOMATO_P_BLOCK_KIND, $AUTOMATO_P_WORK_BLOCK,
+                                        $AUTOMATO_P_BLOCK_DURATION and $AUTOMATO_P_ELAPSED (both in seconds)
+  settings set hook-timeout <HH:MM:SS|default>
+                                        Stop hooks that run for longer than this (10 seconds by default)
+  settings set sound-players <COMMANDS|default>
+                                        The players to try, in order, when sounds can't be played natively,
+                                        separated by commas, e.g. 'paplay, ffplay -nodisp -autoexit'
+  help                                  Show this message";
+
+/// Removes a leading `--data-dir <DIR>` from `args`, as it applies to the menus as well as every command.
+pub fn take_data_dir(args: &mut Vec<String>) -> Result<Option<PathBuf>, PlainTextError> {
+    let Some(first) = args.first() else {
+        return Ok(None);
+    };
+
+    if let Some(dir) = first.strip_prefix("--data-dir=") {
+        let dir = PathBuf::from(dir);
+        args.remove(0);
+
+        return Ok(Some(dir));
+    }
+
+    if first != "--data-dir" {
+        return Ok(None);
+    }
+
+    if args.len() < 2 {
+        return Err(PlainTextError(format!("--data-dir needs a directory (or set ${DATA_DIR_ENV})")));
+    }
+
+    let dir = PathBuf::from(args.remove(1));
+    args.remove(0);
+
+    Ok(Some(dir))
+}
+
+/// Runs the command in `args` (not including the program name) without going through the menus.
+pub fn run(args: &[String], dirs: &DataDirs) -> Result<(), PlainTextError> {
+    let Some((command, rest)) = args.split_first() else {
+        return Err(PlainTextError(String::from("no command given")));
+    };
+
+    match command.as_ref() {
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        "list" => list(dirs, Args::parse(rest, &[])?),
+        "start" => start(dirs, Args::parse(rest, &[])?),
+        "create" => create(dirs, Args::parse(rest, &["name", "work", "rest", "blocks", "long-rest", "phases"])?),
+        "delete" => delete(dirs, Args::parse(rest, &[])?),
+        "export" => export(dirs, Args::parse(rest, &["format", "output"])?),
+        "import" => import(dirs, Args::parse(rest, &["format", "on-conflict"])?),
+        "calendar" => calendar(dirs, Args::parse(rest, &["start", "blocks", "output"])?),
+        "stats" => stats(dirs, Args::parse(rest, &["format"])?),
+        "settings" => settings(dirs, Args::parse(rest, &[])?),
+        "daemon" => run_daemon(dirs, Args::parse(rest, &[])?),
+        "ctl" => ctl(dirs, Args::parse(rest, &["format"])?),
+        "status" => status(dirs, Args::parse_with_flags(rest, &["format", "template", "idle"], &["follow"])?),
+        _ => Err(PlainTextError(format!("unknown command '{command}', try 'automato-p help'"))),
+    }
+}
+
+/// Starts the app up, passing on anything it had to tell the user through stderr so it doesn't end up in piped output.
+fn load(dirs: &DataDirs) -> Result<AppData, PlainTextError> {
+    let mut app_data = app::startup(dirs)?;
+
+    for notice in app_data.take_notices() {
+        eprintln!("{notice}");
+    }
+
+    Ok(app_data)
+}
+
+fn find_schedule(app_data: &AppData, query: &str) -> Result<usize, PlainTextError> {
+    app_data.find_schedule(query)
+        .ok_or_else(|| PlainTextError(format!("no schedule is named or numbered '{query}'")))
+}
+
+fn list(dirs: &DataDirs, args: Args) -> Result<(), PlainTextError> {
+    args.finish()?;
+
+    load(dirs)?.display_schedule_list();
+    Ok(())
+}
+
+fn start(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let query = args.require_positional("schedule name or index")?;
+    args.finish()?;
+
+    let app_data = load(dirs)?;
+    let index = find_schedule(&app_data, &query)?;
+
+    app_data.start_schedule(index)?;
+    Ok(())
+}
+
+/// Blocks that are over as soon as they start would just flash past, so zero isn't allowed.
+fn parse_dur(value: &str, what: &str) -> Result<std::time::Duration, PlainTextError> {
+    match format::try_hhmmss_to_dur(value) {
+        Some(dur) if !dur.is_zero() => Ok(dur),
+        _ => Err(PlainTextError(format!("'{value}' must be an HH:MM:SS duration longer than zero - {what}"))),
+    }
+}
+
+fn parse_blocks(value: &str, what: &str) -> Result<u32, PlainTextError> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(PlainTextError(format!("'{value}' must be a positive integer - {what}"))),
+    }
+}
+
+fn create(dirs: &DataDirs, args: Args) -> Result<(), PlainTextError> {
+    if let Some(spec) = args.option("phases") {
+        if ["work", "rest", "blocks", "long-rest"].iter().any(|name| args.option(name).is_some()) {
+            return Err(PlainTextError(String::from("--phases can't be combined with --work, --rest, --blocks or --long-rest")));
+        }
+
+        let phases = phase::parse(spec).map_err(|e| PlainTextError(format!("'{spec}' could not be read as phases - {e}")))?;
+        let name = args.option("name").unwrap_or("Phases").to_string();
+
+        return push_created(dirs, args, Schedule::from_phases(name, phases));
+    }
+
+    let work = args.option("work").ok_or_else(|| PlainTextError(String::from("missing --work")))?;
+    let rest = args.option("rest").ok_or_else(|| PlainTextError(String::from("missing --rest")))?;
+
+    let work_duration = parse_dur(work, "duration of work block")?;
+    let rest_duration = parse_dur(rest, "duration of rest block")?;
+
+    let repeat_type = match args.option("blocks") {
+        Some(blocks) => RepeatType::Finite(parse_blocks(blocks, "number of blocks before schedule stops")?),
+        None => RepeatType::Infinite,
+    };
+
+    let rest_type = match args.option("long-rest") {
+        Some(long_rest) => {
+            let (blocks, dur) = long_rest.split_once('x')
+                .ok_or_else(|| PlainTextError(format!("'{long_rest}' should look like <blocks>x<HH:MM:SS>, e.g. 4x30:00")))?;
+
+            RestType::LongRest {
+                blocks_per_long_rest: parse_blocks(blocks, "number of blocks per long rest")?,
+                long_rest_duration: parse_dur(dur, "long rest duration")?,
+            }
+        }
+        None => RestType::Standard,
+    };
+
+    let name = match args.option("name") {
+        Some(name) => name.to_string(),
+        None => format!("{}/{}", format::dur_to_xhxmxs(work_duration), format::dur_to_xhxmxs(rest_duration)),
+    };
+
+    let schedule = Schedule { name, work_duration, rest_duration, repeat_type, rest_type, phases: None, sounds: Default::default(), warnings: None, hooks: Default::default() };
+
+    push_created(dirs, args, schedule)
+}
+
+fn push_created(dirs: &DataDirs, args: Args, schedule: Schedule) -> Result<(), PlainTextError> {
+    args.finish()?;
+
+    let mut app_data = load(dirs)?;
+    let taken: Vec<String> = (0..app_data.num_schedules()).map(|i| app_data.get_schedule(i).name.clone()).collect();
+    check_new_name(&schedule.name, &taken)?;
+
+    let created = schedule.to_string();
+    app_data.push_schedule(schedule)?;
+    println!("Created {created}");
+
+    Ok(())
+}
+
+/// Schedules are looked up by name or index everywhere else, so a new name can't be taken already (the way `import`
+/// treats it as a conflict) or be all digits.
+fn check_new_name(name: &str, taken: &[String]) -> Result<(), PlainTextError> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+        return Err(PlainTextError(format!("'{name}' can't be a name, as it would be taken for an index - pick another with --name")));
+    }
+
+    if taken.iter().any(|t| t.eq_ignore_ascii_case(name)) {
+        return Err(PlainTextError(format!("there's already a schedule called '{name}' - pick another with --name, or delete that one first")));
+    }
+
+    Ok(())
+}
+
+fn delete(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let query = args.require_positional("schedule name or index")?;
+    args.finish()?;
+
+    let mut app_data = load(dirs)?;
+    let index = find_schedule(&app_data, &query)?;
+
+    let deleted = app_data.get_schedule(index).to_string();
+    app_data.remove_schedule(index)?;
+    println!("Deleted {deleted}");
+
+    Ok(())
+}
+
+fn share_format(args: &Args, path: Option<&Path>) -> Result<ShareFormat, PlainTextError> {
+    match args.option("format") {
+        Some(f) => ShareFormat::parse(f).ok_or_else(|| PlainTextError(format!("unknown format '{f}', expected json or toml"))),
+        None => Ok(path.map_or(ShareFormat::Json, ShareFormat::from_path)),
+    }
+}
+
+fn export(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let query = args.next_positional();
+    let output = args.option("output").map(PathBuf::from);
+    let format = share_format(&args, output.as_deref())?;
+    args.finish()?;
+
+    let app_data = load(dirs)?;
+
+    let indices = match query {
+        Some(q) => vec![find_schedule(&app_data, &q)?],
+        None => (0..app_data.num_schedules()).collect(),
+    };
+
+    match output {
+        Some(path) => {
+            app_data.export_schedules(&indices, &path, format)?;
+            eprintln!("Exported {} schedule{} to {}", indices.len(), if indices.len() == 1 {""} else {"s"}, path.display());
+        }
+        None => {
+            let schedules: Vec<&Schedule> = indices.iter().map(|&i| app_data.get_schedule(i)).collect();
+            print!("{}", schedule_share::to_string(&schedules, format).map_err(|e| PlainTextError(format!("could not export schedules: {e}")))?);
+        }
+    }
+
+    Ok(())
+}
+
+fn import(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let path = PathBuf::from(args.require_positional("file to import")?);
+    let format = share_format(&args, Some(&path))?;
+
+    let on_conflict = args.option("on-conflict").unwrap_or("skip").to_string();
+    args.finish()?;
+
+    if !["rename", "overwrite", "skip"].contains(&on_conflict.as_str()) {
+        return Err(PlainTextError(format!("unknown --on-conflict '{on_conflict}', expected rename, overwrite or skip")));
+    }
+
+    let mut app_data = load(dirs)?;
+    let mut renames = Vec::new();
+
+    let summary = {
+        let taken: Vec<String> = (0..app_data.num_schedules()).map(|i| app_data.get_schedule(i).name.clone()).collect();
+
+        app_data.import_schedules(&path, format, |schedule| match on_conflict.as_str() {
+            "overwrite" => ConflictChoice::Overwrite,
+            "rename" => {
+                let name = unused_name(&schedule.name, &taken, &renames);
+                renames.push(name.clone());
+                ConflictChoice::Rename(name)
+            }
+            _ => ConflictChoice::Skip,
+        })?
+    };
+
+    for name in &summary.added {
+        println!("Added {name}");
+    }
+
+    for name in &summary.overwritten {
+        println!("Overwrote {name}");
+    }
+
+    for name in &summary.skipped {
+        println!("Skipped {name}, as there's already a schedule with that name");
+    }
+
+    Ok(())
+}
+
+/// "Name (2)", "Name (3)" and so on, whichever is first to not be taken.
+fn unused_name(name: &str, taken: &[String], renamed: &[String]) -> String {
+    (2..)
+        .map(|n| format!("{name} ({n})"))
+        .find(|candidate| !taken.iter().chain(renamed).any(|t| t.eq_ignore_ascii_case(candidate)))
+        .expect("There should always be an unused number")
+}
+
+/// Reads `YYYY-MM-DD HH:MM` (a `T` between them works too), or `HH:MM` for today.
+fn parse_start(value: &str) -> Result<DateTime<Local>, PlainTextError> {
+    let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            ["%H:%M", "%H:%M:%S"].iter()
+                .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+                .map(|time| Local::now().date_naive().and_time(time))
+        })
+        .ok_or_else(|| PlainTextError(format!("'{value}' should be a start time like 2026-10-19 09:00, or 09:00 for today")))?;
+
+    //Daylight saving can make a local time ambiguous or skip it entirely
+    Local.from_local_datetime(&naive).earliest()
+        .ok_or_else(|| PlainTextError(format!("{value} doesn't exist in your time zone")))
+}
+
+fn calendar(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let query = args.require_positional("schedule to plan")?;
+    let start = match args.option("start") {
+        Some(start) => parse_start(start)?,
+        None => Local::now(),
+    };
+    let work_blocks = args.option("blocks").map(|b| parse_blocks(b, "number of work blocks to plan")).transpose()?;
+    let output = args.option("output").map(PathBuf::from);
+    args.finish()?;
+
+    let app_data = load(dirs)?;
+    let schedule = app_data.get_schedule(find_schedule(&app_data, &query)?);
+
+    let planned = plan::plan(schedule, start, work_blocks)
+        .ok_or_else(|| PlainTextError(format!("{} repeats until it's quit, so --blocks is needed to say how many work blocks to plan", schedule.name)))?;
+
+    let ics = calendar::to_ics(&schedule.name, &planned, Utc::now());
+
+    match output {
+        Some(path) => {
+            app_data.write_export(&path, &ics)?;
+            eprintln!("Planned {} blocks to {}", planned.len(), path.display());
+        }
+        None => print!("{ics}"),
+    }
+
+    Ok(())
+}
+
+fn stats(dirs: &DataDirs, args: Args) -> Result<(), PlainTextError> {
+    let format = match args.option("format") {
+        Some(f) => ReportFormat::parse(f).ok_or_else(|| PlainTextError(format!("unknown format '{f}', expected table, json or csv")))?,
+        None => ReportFormat::Table,
+    };
+
+    args.finish()?;
+
+    let loaded = load(dirs)?.read_history()?;
+
+    for bad in &loaded.bad_lines {
+        eprintln!("Left out a history entry that couldn't be read: {}", bad.error);
+    }
+
+    print!("{}", Stats::from_entries(&loaded.entries, Local::now().date_naive()).render(format));
+    Ok(())
+}
+
+fn run_daemon(dirs: &DataDirs, args: Args) -> Result<(), PlainTextError> {
+    args.finish()?;
+
+    daemon::serve(dirs).map_err(|e| PlainTextError(e.to_string()))
+}
+
+fn ctl(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let action = args.require_positional("action (start, pause, resume, skip, stop or status)")?;
+
+    let request = match action.as_ref() {
+        "start" => Request::Start { schedule: args.require_positional("schedule name or index")? },
+        "pause" => Request::Pause,
+        "resume" => Request::Resume,
+        "skip" => Request::Skip,
+        "stop" => Request::Stop,
+        "status" => Request::Status,
+        _ => return Err(PlainTextError(format!("unknown action '{action}', expected start, pause, resume, skip, stop or status"))),
+    };
+
+    let json = match args.option("format") {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(f) => return Err(PlainTextError(format!("unknown format '{f}', expected text or json"))),
+    };
+
+    args.finish()?;
+
+    let status = client::send(&daemon::socket_path(dirs), &request).map_err(|e| PlainTextError(e.to_string()))?;
+
+    if json {
+        println!("{}", serde_json::to_string(&status).expect("A status should be convertible to JSON"));
+    } else {
+        println!("{}", status.describe());
+    }
+
+    Ok(())
+}
+
+/// How often `status --follow` asks the daemon for a new status, which is as often as the countdown changes.
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+fn status(dirs: &DataDirs, args: Args) -> Result<(), PlainTextError> {
+    let format = match args.option("format") {
+        Some(f) => StatusFormat::parse(f).ok_or_else(|| PlainTextError(format!("unknown format '{f}', expected plain, waybar or tmux")))?,
+        None => StatusFormat::Plain,
+    };
+
+    let template = Template::parse(args.option("template").unwrap_or(status_bar::DEFAULT_TEMPLATE)).map_err(PlainTextError)?;
+    let bar = StatusBar { format, template, idle: args.option("idle").unwrap_or_default().to_string() };
+    let follow = args.flag("follow");
+
+    args.finish()?;
+
+    let socket = daemon::socket_path(dirs);
+    let mut stdout = io::stdout();
+
+    loop {
+        //Status bars are usually started before the daemon, so it not running yet is the same as nothing running
+        let status = match client::send(&socket, &Request::Status) {
+            Ok(status) => status,
+            Err(DaemonError::NotRunning(_)) => Status::idle(),
+            Err(e) if follow => {
+                eprintln!("automato-p: {e}");
+                Status::idle()
+            }
+            Err(e) => return Err(PlainTextError(e.to_string())),
+        };
+
+        //The status bar going away closes the pipe, which is the cue to stop following
+        if writeln!(stdout, "{}", bar.render(&status)).and_then(|()| stdout.flush()).is_err() || !follow {
+            return Ok(());
+        }
+
+        thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+fn settings(dirs: &DataDirs, mut args: Args) -> Result<(), PlainTextError> {
+    let Some(action) = args.next_positional() else {
+        let app_data = load(dirs)?;
+
+        println!("sound-path: {}", app_data.get_sound_path().unwrap_or("none"));
+        println!("notifications: {}", if app_data.notifications_enabled() {"on"} else {"off"});
+        for cue in Cue::ALL {
+            let sound = match app_data.sounds().get(cue) {
+                Some(CueSound::File(path)) => path.as_str(),
+                Some(CueSound::Silent) => "silent",
+                None => "default",
+            };
+
+            println!("{}: {sound}", cue.setting_name());
+        }
+
+        let playback = app_data.sounds().playback();
+        println!("volume: {}%", playback.volume);
+        println!("sound-repeat: {}", playback.repeat);
+        println!("warnings: {}", warning::to_list(app_data.warnings()));
+        println!("sound-players: {}", app_data.sound_players().join(", "));
+        println!("on-sleep: {}", app_data.on_sleep().setting_name());
+        for event in HookEvent::ALL {
+            println!("{}: {}", event.setting_name(), app_data.hooks().command(event).unwrap_or("none"));
+        }
+
+        println!("hook-timeout: {}", format::dur_to_hhmmss(app_data.hook_timeout()));
+        return Ok(());
+    };
+
+    if action != "set" {
+        return Err(PlainTextError(format!("unknown settings action '{action}', expected 'set'")));
+    }
+
+    let key = args.require_positional("setting name")?;
+    let value = args.require_positional("setting value")?;
+    args.finish()?;
+
+    let mut app_data = load(dirs)?;
+
+    match key.as_ref() {
+        "sound-path" => {
+            if value.eq_ignore_ascii_case("none") {
+                app_data.set_sound_path(None)?;
+                println!("A sound will no longer play when an alarm ends.");
+            } else if Path::new(&value).exists() {
+                app_data.set_sound_path(Some(value))?;
+                println!("The sound at that path will be played for every transition that doesn't have a sound of its own.");
+            } else {
+                return Err(PlainTextError(format!("'{value}' does not exist")));
+            }
+        }
+        "notifications" => {
+            let enabled = match value.to_ascii_lowercase().as_ref() {
+                "on" | "true" | "yes" => true,
+                "off" | "false" | "no" => false,
+                _ => return Err(PlainTextError(format!("'{value}' should be on or off"))),
+            };
+
+            app_data.set_notifications(enabled)?;
+            println!("Desktop notifications are now {}.", if enabled {"on"} else {"off"});
+        }
+        "sound-players" => {
+            if value.eq_ignore_ascii_case("default") {
+                app_data.set_sound_players(None)?;
+            } else {
+                let players = sound::parse_players(&value)
+                    .ok_or_else(|| PlainTextError(String::from("expected at least one player command")))?;
+                app_data.set_sound_players(Some(players))?;
+            }
+
+            println!("Sounds will fall back on {}.", app_data.sound_players().join(", "));
+        }
+        "warnings" => {
+            let warnings = if value.eq_ignore_ascii_case("none") {
+                Vec::new()
+            } else {
+                warning::parse(&value).map_err(PlainTextError)?
+            };
+
+            app_data.set_warnings(warnings)?;
+            println!("{}", warning::describe(app_data.warnings()));
+        }
+        "on-sleep" => {
+            let policy = SleepPolicy::from_setting_name(&value)
+                .ok_or_else(|| PlainTextError(format!("'{value}' should be count, pause or ask")))?;
+
+            app_data.set_on_sleep(policy)?;
+            println!("When the computer wakes up, {}.", policy.describe());
+        }
+        "hook-timeout" => {
+            let timeout = if value.eq_ignore_ascii_case("default") {
+                None
+            } else {
+                match format::try_hhmmss_to_dur(&value) {
+                    Some(timeout) if !timeout.is_zero() => Some(timeout),
+                    _ => return Err(PlainTextError(format!("'{value}' should be an HH:MM:SS duration longer than zero, or default"))),
+                }
+            };
+
+            app_data.set_hook_timeout(timeout)?;
+            println!("Hooks will be stopped if they run for longer than {}.", format::dur_to_xhxmxs(app_data.hook_timeout()));
+        }
+        "volume" | "sound-repeat" => {
+            let mut sounds = app_data.sounds().clone();
+            let (min, max) = if key == "volume" {(0, 100)} else {(1, MAX_REPEAT)};
+
+            let number = if value.eq_ignore_ascii_case("default") {
+                None
+            } else {
+                match value.parse::<u32>() {
+                    Ok(number) if (min..=max).contains(&number) => Some(number),
+                    _ => return Err(PlainTextError(format!("'{value}' should be a number from {min} to {max}, or default"))),
+                }
+            };
+
+            if key == "volume" {
+                sounds.volume = number.map(|volume| volume as u8);
+            } else {
+                sounds.repeat = number;
+            }
+
+            app_data.set_sounds(sounds)?;
+
+            let playback = app_data.sounds().playback();
+            println!("Sounds will play at {}% volume, {} time(s) in a row.", playback.volume, playback.repeat);
+        }
+        _ => match Cue::from_setting_name(&key) {
+            Some(cue) => {
+                let mut sounds = app_data.sounds().clone();
+
+                let sound = if value.eq_ignore_ascii_case("default") {
+                    None
+                } else if value.eq_ignore_ascii_case("silent") {
+                    Some(CueSound::Silent)
+                } else if Path::new(&value).exists() {
+                    Some(CueSound::File(value))
+                } else {
+                    return Err(PlainTextError(format!("'{value}' does not exist")));
+                };
+
+                sounds.set(cue, sound);
+                app_data.set_sounds(sounds)?;
+
+                match app_data.schedule_sound_defaults().path(cue) {
+                    Some(path) => println!("The sound at {} will be played when {}.", path.display(), cue.describe()),
+                    None => println!("No sound will be played when {}.", cue.describe()),
+                }
+            }
+            None => match HookEvent::from_setting_name(&key) {
+                Some(event) => {
+                    let mut hooks = app_data.hooks().clone();
+                    hooks.set(event, if value.eq_ignore_ascii_case("none") {None} else {Some(value)});
+                    app_data.set_hooks(hooks)?;
+
+                    match app_data.hooks().command(event) {
+                        Some(command) => println!("'{command}' will be run when {}.", event.describe()),
+                        None => println!("Nothing will be run when {}.", event.describe()),
+                    }
+                }
+                None => return Err(PlainTextError(format!("unknown setting '{key}'"))),
+            },
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn a_leading_data_dir_should_be_taken_out_of_the_arguments() {
+        let mut given = args(&["--data-dir", "/tmp/pomo", "list"]);
+        assert_eq!(take_data_dir(&mut given).unwrap(), Some(PathBuf::from("/tmp/pomo")));
+        assert_eq!(given, args(&["list"]));
+
+        let mut given = args(&["--data-dir=/tmp/pomo"]);
+        assert_eq!(take_data_dir(&mut given).unwrap(), Some(PathBuf::from("/tmp/pomo")));
+        assert!(given.is_empty());
+
+        //Only a leading one counts, as anywhere else it could be a value meant for a command
+        let mut given = args(&["list", "--data-dir", "/tmp/pomo"]);
+        assert_eq!(take_data_dir(&mut given).unwrap(), None);
+        assert_eq!(given.len(), 3);
+
+        assert!(take_data_dir(&mut args(&["--data-dir"])).is_err());
+        assert_eq!(take_data_dir(&mut Vec::new()).unwrap(), None);
+    }
+
+    #[test]
+    fn created_schedules_should_need_a_name_of_their_own() {
+        let taken = args(&["Pomodoro", "25m/5m"]);
+
+        assert!(check_new_name("Deep work", &taken).is_ok());
+        assert!(check_new_name("Route 66", &taken).is_ok());
+        assert!(check_new_name("25m/5m", &taken).is_err());
+        assert!(check_new_name("pomodoro", &taken).is_err());
+        assert!(check_new_name("42", &taken).is_err());
+    }
+}
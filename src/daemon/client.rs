@@ -0,0 +1,60 @@
+use std::{error::Error, fmt::Display, io::{self, BufRead, BufReader, Write}, os::unix::net::UnixStream, path::{Path, PathBuf}};
+
+use super::{protocol::{Request, Response, Status}, CLIENT_TIMEOUT};
+
+/// Something went wrong talking to the daemon, or it turned the request down.
+#[derive(Debug)]
+pub enum DaemonError {
+    /// Nothing is listening on the socket.
+    NotRunning(PathBuf),
+    Io(io::Error),
+    /// The daemon sent back something that isn't a response, which most likely means it's a different version.
+    BadResponse(String),
+    /// The daemon understood the request, but couldn't do it.
+    Refused(String),
+    /// Another daemon is already listening on the socket.
+    AlreadyRunning(PathBuf),
+    /// The socket couldn't be set up for the daemon to listen on.
+    Listen {
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+impl Display for DaemonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DaemonError::NotRunning(path) => write!(f, "the daemon isn't running (nothing is listening on {}), start it with 'automato-p daemon'", path.display()),
+            DaemonError::Io(e) => write!(f, "could not talk to the daemon: {e}"),
+            DaemonError::BadResponse(reason) => write!(f, "the daemon sent back something unexpected: {reason}"),
+            DaemonError::Refused(message) => write!(f, "{message}"),
+            DaemonError::AlreadyRunning(path) => write!(f, "a daemon is already listening on {}", path.display()),
+            DaemonError::Listen { path, source } => write!(f, "could not listen on {}: {source}", path.display()),
+        }
+    }
+}
+
+impl Error for DaemonError {}
+
+/// Sends `request` to the daemon listening on `socket`, returning the status it's left in.
+pub fn send(socket: &Path, request: &Request) -> Result<Status, DaemonError> {
+    let mut stream = UnixStream::connect(socket).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => DaemonError::NotRunning(socket.to_path_buf()),
+        _ => DaemonError::Io(e),
+    })?;
+
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).map_err(DaemonError::Io)?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT)).map_err(DaemonError::Io)?;
+
+    let line = serde_json::to_string(request).map_err(|e| DaemonError::Io(e.into()))? + "\n";
+    stream.write_all(line.as_bytes()).map_err(DaemonError::Io)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).map_err(DaemonError::Io)?;
+
+    match serde_json::from_str(&response) {
+        Ok(Response::Ok { status }) => Ok(status),
+        Ok(Response::Error { message }) => Err(DaemonError::Refused(message)),
+        Err(e) => Err(DaemonError::BadResponse(e.to_string())),
+    }
+}
@@ -0,0 +1,330 @@
+pub mod client;
+pub mod protocol;
+pub mod status_bar;
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{mpsc::{self, Receiver, RecvTimeoutError, Sender}, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    app::{self, data_dir::DataDirs, EXPECT_VERIFIED},
+    schedule::{controls::{Control, Controls}, render::{self, Renderer}, runner::{BlockKind, Tick}},
+};
+use client::DaemonError;
+use protocol::{Request, Response, Session, State, Status};
+
+pub const SOCKET_FILE: &str = "daemon.sock";
+
+/// How long either end waits to hear back, so a client that hangs can't hold up everyone else.
+pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a request waits for its control to be applied. Usually that's the next tick, but finishing a schedule
+/// takes as long as the congratulations would have been shown for, and there's no need to wait for that.
+const APPLIED_TIMEOUT: Duration = Duration::from_secs(1);
+
+const EXPECT_UNPOISONED: &str = "Nothing should panic while holding the daemon or its status";
+
+pub fn socket_path(dirs: &DataDirs) -> PathBuf {
+    dirs.runtime.join(SOCKET_FILE)
+}
+
+/// A control for the running schedule, and where to say once it's been applied.
+type Remote = (Control, Sender<()>);
+
+/// What the running schedule was doing as of its last tick.
+struct Live {
+    schedule: String,
+    started_at: DateTime<Local>,
+    block: String,
+    kind: BlockKind,
+    position: usize,
+    total_blocks: Option<usize>,
+    remaining: Duration,
+    paused: bool,
+    elapsed: Duration,
+    ticked_at: Instant,
+}
+
+impl Live {
+    /// The status as of `now`, which is usually a little after the last tick.
+    fn status(&self, now: Instant) -> Status {
+        let since = if self.paused {Duration::ZERO} else {now.saturating_duration_since(self.ticked_at).min(self.remaining)};
+
+        Status {
+            state: if self.paused {State::Paused} else {State::Running},
+            session: Some(Session {
+                schedule: self.schedule.clone(),
+                block: self.block.clone(),
+                kind: self.kind,
+                block_number: self.position + 1,
+                total_blocks: self.total_blocks,
+                remaining: render::round_up_to_second(self.remaining - since),
+                elapsed: self.elapsed + since,
+                started_at: self.started_at,
+            }),
+        }
+    }
+}
+
+type Shared = Arc<Mutex<Option<Live>>>;
+
+/// Keeps the shared status up to date as the schedule runs.
+struct Publisher {
+    live: Shared,
+    schedule: String,
+    started_at: DateTime<Local>,
+    total_blocks: Option<usize>,
+}
+
+impl Publisher {
+    fn publish(&self, live: Option<Live>) {
+        *self.live.lock().expect(EXPECT_UNPOISONED) = live;
+    }
+}
+
+impl Renderer for Publisher {
+    fn tick(&mut self, tick: &Tick) {
+        self.publish(Some(Live {
+            schedule: self.schedule.clone(),
+            started_at: self.started_at,
+            block: tick.block.name(),
+            kind: tick.block.kind,
+            position: tick.position,
+            total_blocks: self.total_blocks,
+            remaining: tick.remaining,
+            paused: tick.paused,
+            elapsed: tick.elapsed,
+            ticked_at: Instant::now(),
+        }));
+    }
+
+    fn completed(&mut self) {
+        self.publish(None);
+    }
+
+    fn aborted(&mut self) {
+        self.publish(None);
+    }
+}
+
+/// Controls sent over the socket. Each one has been applied by the time the runner asks for the next, which is when it's acknowledged.
+struct RemoteControls {
+    receiver: Receiver<Remote>,
+    applied: Option<Sender<()>>,
+}
+
+impl Controls for RemoteControls {
+    fn poll(&mut self, timeout: Duration) -> Option<Control> {
+        if let Some(applied) = self.applied.take() {
+            applied.send(()).ok();
+        }
+
+        match self.receiver.recv_timeout(timeout) {
+            Ok((control, applied)) => {
+                self.applied = Some(applied);
+                Some(control)
+            }
+            Err(RecvTimeoutError::Timeout) => None,
+            //Nobody can send anything anymore, but the schedule still has to be given its time
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(timeout);
+                None
+            }
+        }
+    }
+}
+
+/// The thread running the current schedule, which carries on for a moment after the schedule ends to save it to the history.
+struct Worker {
+    controls: Sender<Remote>,
+    thread: JoinHandle<()>,
+}
+
+struct Daemon {
+    dirs: DataDirs,
+    live: Shared,
+    worker: Option<Worker>,
+}
+
+impl Daemon {
+    fn status(&self) -> Status {
+        match &*self.live.lock().expect(EXPECT_UNPOISONED) {
+            Some(live) => live.status(Instant::now()),
+            None => Status::idle(),
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> Result<Status, String> {
+        match request {
+            Request::Start { schedule } => self.start(&schedule)?,
+            Request::Pause => self.control(Control::TogglePause, &[State::Running], "it's already paused")?,
+            Request::Resume => self.control(Control::TogglePause, &[State::Paused], "it isn't paused")?,
+            Request::Skip => self.control(Control::Skip, &[State::Running, State::Paused], "")?,
+            Request::Stop => {
+                self.control(Control::Abort, &[State::Running, State::Paused], "")?;
+                self.finish_worker();
+            }
+            Request::Status => (),
+        }
+
+        Ok(self.status())
+    }
+
+    /// Waits for the last schedule to be saved to the history, if it hasn't been already.
+    fn finish_worker(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            worker.thread.join().ok();
+        }
+    }
+
+    fn start(&mut self, query: &str) -> Result<(), String> {
+        if let Some(session) = self.status().session {
+            return Err(format!("{} is already running, stop it first", session.schedule));
+        }
+
+        self.finish_worker();
+
+        //Loaded fresh every time, so schedules and settings changed since the daemon started are picked up
+        let mut app_data = app::startup(&self.dirs).map_err(|e| e.to_string())?;
+
+        for notice in app_data.take_notices() {
+            eprintln!("{notice}");
+        }
+
+        let index = app_data.find_schedule(query).ok_or_else(|| format!("no schedule is named or numbered '{query}'"))?;
+        let schedule = app_data.get_schedule(index);
+
+        let publisher = Publisher {
+            live: Arc::clone(&self.live),
+            schedule: schedule.name.clone(),
+            started_at: Local::now(),
+            total_blocks: schedule.total_blocks(),
+        };
+
+        println!("Starting {}", schedule.name);
+
+        let (controls, receiver) = mpsc::channel();
+        let (started, on_started) = mpsc::channel();
+        let mut remote = RemoteControls { receiver, applied: Some(started) };
+
+        let thread = thread::spawn(move || {
+            if let Err(e) = app_data.run_detached(index, &mut remote, publisher) {
+                eprintln!("Failed to save the session: {e}");
+            }
+        });
+
+        //Heard back once the first block is under way, or as soon as the thread ends if there was nothing to run
+        on_started.recv().ok();
+        self.worker = Some(Worker { controls, thread });
+
+        Ok(())
+    }
+
+    /// Sends `control` to the running schedule if it's in one of the states `when`, waiting until it's been applied.
+    /// `otherwise` is why it can't be done in any other state (apart from idle, when there's nothing to control).
+    fn control(&mut self, control: Control, when: &[State], otherwise: &str) -> Result<(), String> {
+        match self.status().state {
+            State::Idle => return Err(String::from("nothing is running")),
+            state if !when.contains(&state) => return Err(otherwise.to_string()),
+            _ => (),
+        }
+
+        let worker = self.worker.as_ref().expect(EXPECT_VERIFIED);
+        let (applied, on_applied) = mpsc::channel();
+
+        if worker.controls.send((control, applied)).is_ok() {
+            on_applied.recv_timeout(APPLIED_TIMEOUT).ok();
+        }
+
+        Ok(())
+    }
+}
+
+/// Answers every request the client sends, one per line, until it hangs up or goes quiet for too long. The daemon is
+/// only locked while a request is being handled, so a client that's slow to send or read doesn't hold up the others.
+fn serve_client(daemon: &Mutex<Daemon>, stream: UnixStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let mut line = String::new();
+
+    while reader.read_line(&mut line)? > 0 {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => match daemon.lock().expect(EXPECT_UNPOISONED).handle(request) {
+                Ok(status) => Response::Ok { status },
+                Err(message) => Response::Error { message },
+            },
+            Err(e) => Response::Error { message: format!("not a valid request: {e}") },
+        };
+
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+        line.clear();
+    }
+
+    Ok(())
+}
+
+/// Takes over the socket, unless another daemon is still listening on it. A socket file left behind by a daemon
+/// that was killed is replaced.
+fn bind(path: &Path) -> Result<UnixListener, DaemonError> {
+    let listen_error = |source| DaemonError::Listen { path: path.to_path_buf(), source };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(listen_error)?;
+    }
+
+    match UnixListener::bind(path) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(DaemonError::AlreadyRunning(path.to_path_buf()));
+            }
+
+            fs::remove_file(path).map_err(listen_error)?;
+            UnixListener::bind(path).map_err(listen_error)
+        }
+        bound => bound.map_err(listen_error),
+    }
+}
+
+/// Runs schedules in the background, one at a time, as clients ask for them over the socket. Only returns if the socket can't be listened on.
+pub fn serve(dirs: &DataDirs) -> Result<(), DaemonError> {
+    let path = socket_path(dirs);
+    let listener = bind(&path)?;
+
+    println!("Listening on {}", path.display());
+
+    //Old ./user files are left for the app to move, as the daemon could have been started from anywhere
+    let dirs = DataDirs { legacy: None, ..dirs.clone() };
+    let daemon = Arc::new(Mutex::new(Daemon { dirs, live: Shared::default(), worker: None }));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Lost a client: {e}");
+                continue;
+            }
+        };
+
+        //Each on its own thread, so a client that keeps its connection open (or hangs) doesn't stop anyone else getting through
+        let daemon = Arc::clone(&daemon);
+
+        thread::spawn(move || {
+            if let Err(e) = serve_client(&daemon, stream) {
+                eprintln!("Lost a client: {e}");
+            }
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+
+use crate::schedule::{format, runner::BlockKind};
+
+/// Sent by clients, one JSON object per line, like `{"command":"start","schedule":"Pomodoro"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    /// `schedule` is a name or an index, like on the command line.
+    Start { schedule: String },
+    Pause,
+    Resume,
+    Skip,
+    Stop,
+    Status,
+}
+
+/// Sent back for every request, one JSON object per line. Anything that succeeds gets the status it left the daemon in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub enum Response {
+    Ok { status: Status },
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum State {
+    Idle,
+    Running,
+    Paused,
+}
+
+/// What the daemon is up to. Everything but `state` is left out while it's idle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    pub state: State,
+    #[serde(flatten)]
+    pub session: Option<Session>,
+}
+
+/// The schedule the daemon is running. Durations are in whole seconds, so scripts don't have to deal with fractions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub schedule: String,
+    /// What the block is called, like "Work block 3".
+    pub block: String,
+    pub kind: BlockKind,
+    /// Counting from 1.
+    pub block_number: usize,
+    /// None for schedules that go on until they're stopped.
+    pub total_blocks: Option<usize>,
+    #[serde(with = "secs")]
+    pub remaining: Duration,
+    /// Not counting pauses.
+    #[serde(with = "secs")]
+    pub elapsed: Duration,
+    pub started_at: DateTime<Local>,
+}
+
+impl Status {
+    pub fn idle() -> Status {
+        Status { state: State::Idle, session: None }
+    }
+
+    /// One line for people rather than scripts, like "Pomodoro: Work block 3 (3 of 15), 12:34 left".
+    pub fn describe(&self) -> String {
+        let Some(session) = &self.session else {
+            return String::from("Nothing is running.");
+        };
+
+        let position = match session.total_blocks {
+            Some(total) => format!("{} of {total}", session.block_number),
+            None => session.block_number.to_string(),
+        };
+
+        format!("{}: {} ({position}), {} left{}",
+            session.schedule,
+            session.block,
+            format::dur_to_hhmmss(session.remaining),
+            if self.state == State::Paused {" (paused)"} else {""},
+        )
+    }
+}
+
+mod secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(dur: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(dur.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_should_be_plain_json_objects() {
+        let start: Request = serde_json::from_str(r#"{"command":"start","schedule":"Pomodoro"}"#).unwrap();
+        assert_eq!(start, Request::Start { schedule: String::from("Pomodoro") });
+        assert_eq!(serde_json::to_string(&Request::Skip).unwrap(), r#"{"command":"skip"}"#);
+
+        let idle = Response::Ok { status: Status::idle() };
+        assert_eq!(serde_json::to_string(&idle).unwrap(), r#"{"result":"ok","status":{"state":"idle"}}"#);
+
+        let running = Response::Ok { status: Status {
+            state: State::Paused,
+            session: Some(Session {
+                schedule: String::from("Pomodoro"),
+                block: String::from("Work block 1"),
+                kind: BlockKind::Work,
+                block_number: 1,
+                total_blocks: Some(15),
+                remaining: Duration::from_secs(754),
+                elapsed: Duration::from_secs(746),
+                started_at: Local::now(),
+            }),
+        }};
+
+        let json = serde_json::to_string(&running).unwrap();
+        assert!(json.contains(r#""state":"paused","schedule":"Pomodoro""#) && json.contains(r#""remaining":754"#), "{json}");
+        assert_eq!(serde_json::from_str::<Response>(&json).unwrap(), running);
+
+        let Response::Ok { status } = running else { unreachable!() };
+        assert_eq!(status.describe(), "Pomodoro: Work block 1 (1 of 15), 12:34 left (paused)");
+    }
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::schedule::{format, runner::BlockKind};
+use super::protocol::{Session, State, Status};
+
+pub const DEFAULT_TEMPLATE: &str = "{block} {remaining}";
+
+/// Everything a template can fill in.
+pub const PLACEHOLDERS: [&str; 9] = ["schedule", "block", "kind", "state", "position", "remaining", "remaining_short", "elapsed", "elapsed_short"];
+
+/// A line of text with `{placeholder}`s in it, like "{block} {remaining}".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template(String);
+
+impl Template {
+    /// Errors with the first placeholder that isn't one of `PLACEHOLDERS`, or a brace that's never closed.
+    pub fn parse(template: &str) -> Result<Template, String> {
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                return Err(format!("'{{' at '{}' is never closed", &rest[start..]));
+            };
+
+            let name = &rest[start + 1..start + len];
+
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!("unknown placeholder '{{{name}}}', expected one of {}", PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", ")));
+            }
+
+            rest = &rest[start + len + 1..];
+        }
+
+        Ok(Template(template.to_string()))
+    }
+
+    pub fn fill(&self, status: &Status, session: &Session) -> String {
+        PLACEHOLDERS.iter().fold(self.0.clone(), |text, name| {
+            let key = format!("{{{name}}}");
+
+            if text.contains(&key) {
+                text.replace(&key, &value(name, status, session))
+            } else {
+                text
+            }
+        })
+    }
+}
+
+fn value(name: &str, status: &Status, session: &Session) -> String {
+    match name {
+        "schedule" => session.schedule.clone(),
+        "block" => session.block.clone(),
+        "kind" => kind_name(session.kind).to_string(),
+        "state" => state_name(status.state).to_string(),
+        "position" => match session.total_blocks {
+            Some(total) => format!("{}/{total}", session.block_number),
+            None => session.block_number.to_string(),
+        },
+        "remaining" => format::dur_to_hhmmss(session.remaining),
+        "remaining_short" => short(session.remaining),
+        "elapsed" => format::dur_to_hhmmss(session.elapsed),
+        "elapsed_short" => short(session.elapsed),
+        _ => unreachable!("Templates should only have known placeholders"),
+    }
+}
+
+/// `dur_to_xhxmxs` leaves nothing at all for zero.
+fn short(dur: Duration) -> String {
+    match format::dur_to_xhxmxs(dur) {
+        s if s.is_empty() => String::from("0s"),
+        s => s,
+    }
+}
+
+fn kind_name(kind: BlockKind) -> &'static str {
+    match kind {
+        BlockKind::Work => "work",
+        BlockKind::Rest => "rest",
+        BlockKind::LongRest => "long-rest",
+    }
+}
+
+fn state_name(state: State) -> &'static str {
+    match state {
+        State::Idle => "idle",
+        State::Running => "running",
+        State::Paused => "paused",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFormat {
+    /// Just the template.
+    Plain,
+    /// A JSON object for waybar's custom modules, which can be styled by its `class`.
+    Waybar,
+    /// The template coloured by the kind of block, for tmux's status-left or status-right.
+    Tmux,
+}
+
+impl StatusFormat {
+    pub fn parse(str: &str) -> Option<StatusFormat> {
+        match str.to_ascii_lowercase().as_ref() {
+            "plain" => Some(StatusFormat::Plain),
+            "waybar" => Some(StatusFormat::Waybar),
+            "tmux" => Some(StatusFormat::Tmux),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WaybarOutput {
+    text: String,
+    tooltip: String,
+    alt: &'static str,
+    class: Vec<&'static str>,
+}
+
+/// Renders statuses as single lines for status bars. `idle` is shown when nothing is running.
+pub struct StatusBar {
+    pub format: StatusFormat,
+    pub template: Template,
+    pub idle: String,
+}
+
+impl StatusBar {
+    pub fn render(&self, status: &Status) -> String {
+        let text = match &status.session {
+            Some(session) => self.template.fill(status, session),
+            None => self.idle.clone(),
+        };
+
+        match self.format {
+            StatusFormat::Plain => text,
+            StatusFormat::Waybar => {
+                let alt = status.session.as_ref().map_or("idle", |session| kind_name(session.kind));
+                let mut class = vec![alt];
+
+                if status.state == State::Paused {
+                    class.push("paused");
+                }
+
+                let output = WaybarOutput { text, tooltip: status.describe(), alt, class };
+                serde_json::to_string(&output).expect("Waybar output should be convertible to JSON")
+            }
+            StatusFormat::Tmux => {
+                //tmux treats '#' as the start of a format, so any in names have to be doubled up
+                let text = text.replace('#', "##");
+
+                let colour = match &status.session {
+                    None => return text,
+                    Some(_) if status.state == State::Paused => "yellow",
+                    Some(session) => match session.kind {
+                        BlockKind::Work => "red",
+                        BlockKind::Rest => "green",
+                        BlockKind::LongRest => "blue",
+                    },
+                };
+
+                format!("#[fg={colour}]{text}#[default]")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use super::*;
+
+    #[test]
+    fn status_bars_should_fill_in_the_template_for_each_format() {
+        let status = Status {
+            state: State::Paused,
+            session: Some(Session {
+                schedule: String::from("Sprint #2"),
+                block: String::from("Work block 3"),
+                kind: BlockKind::Work,
+                block_number: 5,
+                total_blocks: None,
+                remaining: Duration::from_secs(3754),
+                elapsed: Duration::ZERO,
+                started_at: Local::now(),
+            }),
+        };
+
+        let template = Template::parse("{schedule}: {kind} {position} {remaining} ({remaining_short}, {elapsed_short} in) {state}").unwrap();
+        let mut bar = StatusBar { format: StatusFormat::Plain, template, idle: String::new() };
+        assert_eq!(bar.render(&status), "Sprint #2: work 5 1:02:34 (1h2m34s, 0s in) paused");
+
+        bar.template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+        bar.format = StatusFormat::Tmux;
+        assert_eq!(bar.render(&status), "#[fg=yellow]Work block 3 1:02:34#[default]");
+
+        bar.format = StatusFormat::Waybar;
+        let json: serde_json::Value = serde_json::from_str(&bar.render(&status)).unwrap();
+        assert_eq!(json["text"], "Work block 3 1:02:34");
+        assert_eq!(json["class"], serde_json::json!(["work", "paused"]));
+
+        bar.idle = String::from("idle");
+        assert!(bar.render(&Status::idle()).starts_with(r#"{"text":"idle","tooltip":"Nothing is running.","alt":"idle""#));
+
+        assert!(Template::parse("{block} {left}").unwrap_err().contains("'{left}'"));
+        assert!(Template::parse("{block").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+pub mod stats;
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+
+use crate::schedule::{format, runner::{Outcome, RunSummary}};
+
+/// A record of one time a schedule was run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub schedule_name: String,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub work_blocks_completed: u32,
+    /// Older entries don't have this, so it's zero for them.
+    #[serde(default)]
+    pub focused: Duration,
+    pub outcome: Outcome,
+}
+
+impl HistoryEntry {
+    pub fn new(schedule_name: &str, started_at: DateTime<Local>, ended_at: DateTime<Local>, summary: RunSummary) -> HistoryEntry {
+        HistoryEntry {
+            schedule_name: schedule_name.to_string(),
+            started_at,
+            ended_at,
+            work_blocks_completed: summary.work_blocks_completed,
+            focused: summary.focused,
+            outcome: summary.outcome,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        (self.ended_at - self.started_at).to_std().unwrap_or_default()
+    }
+}
+
+pub fn display_history(entries: &[HistoryEntry]) {
+    if entries.is_empty() {
+        println!("You haven't run any schedules yet.");
+        return;
+    }
+
+    println!("{:<17} {:<20} {:>9} {:>11} Result", "Started", "Schedule", "Length", "Work blocks");
+
+    for entry in entries {
+        println!("{:<17} {:<20} {:>9} {:>11} {}",
+            entry.started_at.format("%Y-%m-%d %H:%M").to_string(),
+            truncate(&entry.schedule_name, 20),
+            format::dur_to_hhmmss(entry.duration()),
+            entry.work_blocks_completed,
+            match entry.outcome {
+                Outcome::Finished => "finished",
+                Outcome::Aborted => "aborted",
+            },
+        );
+    }
+}
+
+fn truncate(str: &str, max_chars: usize) -> String {
+    if str.chars().count() <= max_chars {
+        str.to_string()
+    } else {
+        str.chars().take(max_chars - 1).chain(std::iter::once('…')).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn entries_should_round_trip_and_read_older_lines() {
+        let started_at = Local.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap();
+        let summary = RunSummary { outcome: Outcome::Aborted, work_blocks_completed: 2, focused: Duration::from_secs(3000) };
+        let entry = HistoryEntry::new("Pomodoro", started_at, started_at + chrono::Duration::minutes(62), summary);
+
+        let json = serde_json::to_string(&entry).unwrap();
+        let read: HistoryEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), json);
+        assert_eq!(read.duration(), Duration::from_secs(62 * 60));
+        assert!(json.contains(r#""outcome":"Aborted""#), "{json}");
+
+        //From before the focused time was recorded
+        let old = r#"{"schedule_name":"52/17","started_at":"2026-10-01T09:00:00+00:00","ended_at":"2026-10-01T10:00:00+00:00","work_blocks_completed":1,"outcome":"Finished"}"#;
+        let read: HistoryEntry = serde_json::from_str(old).unwrap();
+        assert_eq!((read.focused, read.outcome, read.duration()), (Duration::ZERO, Outcome::Finished, Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn long_names_should_be_truncated_to_fit_their_column() {
+        assert_eq!(truncate("Pomodoro", 8), "Pomodoro");
+        assert_eq!(truncate("Deep work mornings", 10), "Deep work…");
+    }
+}
@@ -0,0 +1,279 @@
+use std::{collections::{BTreeMap, BTreeSet}, fmt::Write, time::Duration};
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use crate::schedule::{format, runner::Outcome};
+use super::HistoryEntry;
+
+/// How many of the most recent days, weeks and months the table shows. JSON and CSV always have all of them.
+const TABLE_DAYS: usize = 7;
+const TABLE_WEEKS: usize = 4;
+const TABLE_MONTHS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn parse(str: &str) -> Option<ReportFormat> {
+        match str.to_ascii_lowercase().as_ref() {
+            "table" => Some(ReportFormat::Table),
+            "json" => Some(ReportFormat::Json),
+            "csv" => Some(ReportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Totals {
+    pub sessions: u32,
+    pub finished: u32,
+    pub aborted: u32,
+    pub work_blocks: u32,
+    pub focused_secs: u64,
+}
+
+impl Totals {
+    fn add(&mut self, entry: &HistoryEntry) {
+        self.sessions += 1;
+        self.work_blocks += entry.work_blocks_completed;
+        self.focused_secs += entry.focused.as_secs();
+
+        match entry.outcome {
+            Outcome::Finished => self.finished += 1,
+            Outcome::Aborted => self.aborted += 1,
+        }
+    }
+
+    pub fn focused(&self) -> Duration {
+        Duration::from_secs(self.focused_secs)
+    }
+
+    pub fn average_blocks(&self) -> f64 {
+        if self.sessions == 0 {
+            0.0
+        } else {
+            f64::from(self.work_blocks) / f64::from(self.sessions)
+        }
+    }
+}
+
+/// Totals for one day (YYYY-MM-DD), ISO week (YYYY-Www) or month (YYYY-MM), or for one schedule.
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+    pub key: String,
+    #[serde(flatten)]
+    pub totals: Totals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub overall: Totals,
+    pub average_blocks_per_session: f64,
+    /// Days in a row, up to today, with at least one completed work block. Today not having one yet doesn't break it.
+    pub current_streak_days: u32,
+    pub longest_streak_days: u32,
+    /// Newest first.
+    pub days: Vec<Group>,
+    pub weeks: Vec<Group>,
+    pub months: Vec<Group>,
+    /// Most focused time first.
+    pub schedules: Vec<Group>,
+}
+
+fn group(entries: &[HistoryEntry], key: impl Fn(&HistoryEntry) -> String) -> BTreeMap<String, Totals> {
+    let mut groups: BTreeMap<String, Totals> = BTreeMap::new();
+
+    for entry in entries {
+        groups.entry(key(entry)).or_default().add(entry);
+    }
+
+    groups
+}
+
+fn newest_first(groups: BTreeMap<String, Totals>) -> Vec<Group> {
+    groups.into_iter().rev().map(|(key, totals)| Group { key, totals }).collect()
+}
+
+fn streaks(worked_days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for &day in worked_days {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let mut current = 0;
+    let mut day = if worked_days.contains(&today) {Some(today)} else {today.pred_opt()};
+
+    while let Some(d) = day.filter(|d| worked_days.contains(d)) {
+        current += 1;
+        day = d.pred_opt();
+    }
+
+    (current, longest)
+}
+
+fn dur_or_zero(dur: Duration) -> String {
+    if dur.as_secs() == 0 {
+        String::from("0m")
+    } else {
+        format::dur_to_xhxmxs(dur)
+    }
+}
+
+fn csv_field(str: &str) -> String {
+    if str.contains([',', '"', '\n']) {
+        format!("\"{}\"", str.replace('"', "\"\""))
+    } else {
+        str.to_string()
+    }
+}
+
+impl Stats {
+    pub fn from_entries(entries: &[HistoryEntry], today: NaiveDate) -> Stats {
+        let mut overall = Totals::default();
+        entries.iter().for_each(|entry| overall.add(entry));
+
+        let worked_days: BTreeSet<NaiveDate> = entries.iter()
+            .filter(|entry| entry.work_blocks_completed > 0)
+            .map(|entry| entry.started_at.date_naive())
+            .collect();
+
+        let (current_streak_days, longest_streak_days) = streaks(&worked_days, today);
+
+        let mut schedules = newest_first(group(entries, |entry| entry.schedule_name.clone()));
+        schedules.sort_by_key(|g| std::cmp::Reverse(g.totals.focused_secs));
+
+        Stats {
+            average_blocks_per_session: overall.average_blocks(),
+            overall,
+            current_streak_days,
+            longest_streak_days,
+            days: newest_first(group(entries, |entry| entry.started_at.format("%Y-%m-%d").to_string())),
+            weeks: newest_first(group(entries, |entry| {
+                let week = entry.started_at.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            })),
+            months: newest_first(group(entries, |entry| entry.started_at.format("%Y-%m").to_string())),
+            schedules,
+        }
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Table => self.to_table(),
+            ReportFormat::Json => serde_json::to_string_pretty(self).expect("Stats should be convertible to JSON"),
+            ReportFormat::Csv => self.to_csv(),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let mut out = String::new();
+
+        if self.overall.sessions == 0 {
+            return String::from("You haven't run any schedules yet.\n");
+        }
+
+        //Writing to a String can't fail
+        writeln!(out, "Sessions: {} ({} completed, {} abandoned)", self.overall.sessions, self.overall.finished, self.overall.aborted).ok();
+        writeln!(out, "Focused time: {}", dur_or_zero(self.overall.focused())).ok();
+        writeln!(out, "Average work blocks per session: {:.1}", self.average_blocks_per_session).ok();
+        writeln!(out, "Daily streak: {} days (longest {} days)", self.current_streak_days, self.longest_streak_days).ok();
+
+        let sections = [
+            ("Day", &self.days, TABLE_DAYS),
+            ("Week", &self.weeks, TABLE_WEEKS),
+            ("Month", &self.months, TABLE_MONTHS),
+            ("Schedule", &self.schedules, self.schedules.len()),
+        ];
+
+        for (title, groups, limit) in sections {
+            writeln!(out, "\n{:<20} {:>9} {:>9} {:>9} {:>11}", title, "Focused", "Sessions", "Abandoned", "Avg blocks").ok();
+
+            for group in groups.iter().take(limit) {
+                writeln!(out, "{:<20} {:>9} {:>9} {:>9} {:>11.1}",
+                    group.key,
+                    dur_or_zero(group.totals.focused()),
+                    group.totals.sessions,
+                    group.totals.aborted,
+                    group.totals.average_blocks(),
+                ).ok();
+            }
+        }
+
+        out
+    }
+
+    /// One row per group, with the overall totals first. Streaks don't fit the table, so they're only in the other formats.
+    fn to_csv(&self) -> String {
+        let mut out = String::from("group,key,sessions,finished,aborted,work_blocks,focused_secs\n");
+
+        let overall = Group { key: String::from("all"), totals: self.overall.clone() };
+        let rows = std::iter::once(("total", &overall))
+            .chain(self.days.iter().map(|g| ("day", g)))
+            .chain(self.weeks.iter().map(|g| ("week", g)))
+            .chain(self.months.iter().map(|g| ("month", g)))
+            .chain(self.schedules.iter().map(|g| ("schedule", g)));
+
+        for (name, group) in rows {
+            let t = &group.totals;
+            writeln!(out, "{name},{},{},{},{},{},{}", csv_field(&group.key), t.sessions, t.finished, t.aborted, t.work_blocks, t.focused_secs).ok();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+
+    fn entry(name: &str, day: u32, blocks: u32, outcome: Outcome) -> HistoryEntry {
+        let started_at = Local.with_ymd_and_hms(2026, 10, day, 9, 0, 0).unwrap();
+
+        HistoryEntry {
+            schedule_name: name.to_string(),
+            started_at,
+            ended_at: started_at + chrono::Duration::hours(1),
+            work_blocks_completed: blocks,
+            focused: Duration::from_secs(25 * 60) * blocks,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn streaks_should_survive_today_having_no_sessions_yet() {
+        let entries = [
+            entry("Pomodoro", 1, 2, Outcome::Finished),
+            entry("Pomodoro", 2, 1, Outcome::Finished),
+            entry("Pomodoro", 3, 4, Outcome::Finished),
+            entry("Pomodoro", 5, 0, Outcome::Aborted),
+            entry("Deep", 6, 1, Outcome::Aborted),
+            entry("Deep", 7, 3, Outcome::Finished),
+        ];
+
+        let stats = Stats::from_entries(&entries, NaiveDate::from_ymd_opt(2026, 10, 8).unwrap());
+
+        assert_eq!(stats.current_streak_days, 2);
+        assert_eq!(stats.longest_streak_days, 3);
+        assert_eq!((stats.overall.finished, stats.overall.aborted), (4, 2));
+        assert_eq!(stats.overall.work_blocks, 11);
+        assert_eq!(stats.schedules[0].key, "Pomodoro", "Pomodoro has the most focused time");
+        assert_eq!(stats.days[0].key, "2026-10-07");
+    }
+}
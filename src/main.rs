@@ -1,12 +1,57 @@
 pub mod app; 
+pub mod cli;
+pub mod daemon;
+pub mod history;
 pub mod schedule;
 pub mod prompts;
+pub mod sound;
+#[cfg(test)]
+mod test_dir;
+
+use std::{env, process};
+
+use app::data_dir::DataDirs;
 
 fn main() {
-    let mut app_data = app::startup();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let dirs = match cli::take_data_dir(&mut args) {
+        Ok(dir) => DataDirs::resolve(dir),
+        Err(e) => {
+            eprintln!("automato-p: {e}");
+            process::exit(1);
+        }
+    };
+
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args, &dirs) {
+            eprintln!("automato-p: {e}");
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    let mut app_data = match app::startup(&dirs) {
+        Ok(app_data) => app_data,
+        Err(e) => {
+            eprintln!("automato-p could not start: {e}");
+            process::exit(1);
+        }
+    };
+
+    let notices = app_data.take_notices();
+
+    if !notices.is_empty() {
+        notices.iter().for_each(|notice| println!("{notice}"));
+        println!("Press enter to continue");
+        app::console::get_input();
+    }
+
+    prompts::resume_session::offer(&mut app_data);
     
     loop {
-        if app::run(&mut app_data) == false {
+        if !app::run(&mut app_data) {
             println!("Are you sure you want to exit the app? (input y to confirm)");
             
             if let Some(true) = app::console::yes_or_no() {
@@ -146,18 +146,18 @@ fn prompt() -> Option<ScheduleCreateResponses> {
 fn try_convert_to_schedule(responses: ScheduleCreateResponses) -> Result<Schedule, String> {
     let mut issues = String::new();
 
-    let work_duration = format::try_hhmmss_to_dur(&responses.work_duration);
+    let work_duration = format::try_hhmmss_to_dur(&responses.work_duration).filter(|dur| !dur.is_zero());
     if work_duration.is_none() {
         issues += &format!(
-            "'{}' could not be converted into an HH:MM:SS duration - duration of work block\n",
+            "'{}' could not be converted into an HH:MM:SS duration longer than zero - duration of work block\n",
             &responses.work_duration
         );
     }
 
-    let rest_duration = format::try_hhmmss_to_dur(&responses.rest_duration);
-    if work_duration.is_none() {
+    let rest_duration = format::try_hhmmss_to_dur(&responses.rest_duration).filter(|dur| !dur.is_zero());
+    if rest_duration.is_none() {
         issues += &format!(
-            "'{}' could not be converted into an HH:MM:SS duration - duration of rest block\n",
+            "'{}' could not be converted into an HH:MM:SS duration longer than zero - duration of rest block\n",
             &responses.rest_duration
         );
     }
@@ -188,34 +188,38 @@ fn try_convert_to_schedule(responses: ScheduleCreateResponses) -> Result<Schedul
             Some(None)
         };
 
-        long_rest_dur = if let Some(d) = format::try_hhmmss_to_dur(&long_rest_duration) {
+        long_rest_dur = if let Some(d) = format::try_hhmmss_to_dur(&long_rest_duration).filter(|dur| !dur.is_zero()) {
             Some(Some(d))
         } else {
-            issues += &format!("'{long_rest_duration}' could not be converted into an HH:MM:SS duration - long rest duration\n");
+            issues += &format!("'{long_rest_duration}' could not be converted into an HH:MM:SS duration longer than zero - long rest duration\n");
 
             Some(None)
         }
     }
 
-    if issues.len() == 0 {
+    if issues.is_empty() {
         Ok(
             Schedule {
                 name: responses.name,
                 work_duration: work_duration.expect(EXPECT_VERIFIED),
                 rest_duration: rest_duration.expect(EXPECT_VERIFIED),
-                repeat_type: if finite_blocks.is_none() {
-                    RepeatType::Infinite
+                repeat_type: if let Some(blocks) = finite_blocks {
+                    RepeatType::Finite(blocks.expect(EXPECT_VERIFIED))
                 } else {
-                    RepeatType::Finite(finite_blocks.expect(EXPECT_VERIFIED).expect(EXPECT_VERIFIED))
+                    RepeatType::Infinite
                 },
-                rest_type: if long_rest_blocks.is_none() {
-                    RestType::Standard
-                } else {
+                rest_type: if let Some(blocks) = long_rest_blocks {
                     RestType::LongRest {
-                        blocks_per_long_rest: long_rest_blocks.expect(EXPECT_VERIFIED).expect(EXPECT_VERIFIED), 
+                        blocks_per_long_rest: blocks.expect(EXPECT_VERIFIED), 
                         long_rest_duration: long_rest_dur.expect(EXPECT_VERIFIED).expect(EXPECT_VERIFIED)
                     }
+                } else {
+                    RestType::Standard
                 },
+                phases: None,
+                sounds: Default::default(),
+                warnings: None,
+                hooks: Default::default(),
             }
         )
     } else {
@@ -229,7 +233,11 @@ pub fn start(app_data: &mut AppData) {
             let result = try_convert_to_schedule(responses);
     
             match result {
-                Ok(schedule) => app_data.push_schedule(schedule),
+                Ok(schedule) => {
+                    if let Err(e) = app_data.push_schedule(schedule) {
+                        println!("Failed to save your new schedule: {e}");
+                    }
+                }
                 Err(issues) => {
                     println!("Failed to convert responses to a schedule, as the following issues were present:");
                     for (line, i) in issues.lines().zip(1u8..) {
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use crate::{app::{console, BACK_CHARACTERS}, schedule::{format, hooks::{HookEvent, HookSet}}};
+
+/// What's run, or what's run instead followed by where that comes from.
+fn describe_hook(hooks: &HookSet, fallback: &HookSet, event: HookEvent, source: &str) -> String {
+    let command = |hooks: &HookSet| hooks.command(event).map_or_else(|| String::from("nothing"), |command| format!("'{command}'"));
+
+    match hooks.get(event) {
+        Some(_) => command(hooks),
+        None => format!("{} ({source})", command(fallback)),
+    }
+}
+
+/// Lets the command run for one of the events in `hooks` be changed. Anything `hooks` doesn't set comes from `fallback`,
+/// and `source` says where that is, like "app's setting". Returns None if they backed out without changing anything.
+pub fn prompt(hooks: &HookSet, fallback: &HookSet, source: &str) -> Option<HookSet> {
+    let mut new_hooks = hooks.clone();
+
+    println!("Which command would you like to change? They're run with sh, and can tell what happened from $AUTOMATO_P_EVENT,");
+    println!("$AUTOMATO_P_SCHEDULE, $AUTOMATO_P_BLOCK and the other variables listed in 'automato-p help'.");
+
+    for (i, event) in HookEvent::ALL.iter().enumerate() {
+        println!("{i}: The command run when {} (currently {})", event.describe(), describe_hook(hooks, fallback, *event, source));
+    }
+
+    let event = loop {
+        let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
+
+        match response.parse::<usize>().ok().and_then(|i| HookEvent::ALL.get(i)) {
+            Some(event) => break *event,
+            None => println!("'{response}' is not a valid response, try again."),
+        }
+    };
+
+    println!("Type the command to run when {}, NONE to run nothing, or DEFAULT to use the {source}.", event.describe());
+
+    match console::get_input_trimmed_exclude(&[&["NONE", "DEFAULT"], &BACK_CHARACTERS[..]].concat(), false) {
+        Ok(command) => new_hooks.set(event, Some(command)),
+        //An empty command stops the fallback's from running too
+        Err(0) => new_hooks.set(event, Some(String::new())),
+        Err(1) => new_hooks.set(event, None),
+        Err(_) => return None,
+    }
+
+    Some(new_hooks)
+}
+
+/// Asks how long hooks can run for. Returns None if they went back, or Some(None) if they typed DEFAULT.
+pub fn prompt_timeout(current: Duration) -> Option<Option<Duration>> {
+    println!("Hooks are currently stopped if they run for longer than {}.", format::dur_to_xhxmxs(current));
+    println!("How long should they be allowed to run for instead (HH:MM:SS)? Type DEFAULT to go back to the usual limit.");
+
+    loop {
+        let response = match console::get_input_trimmed_exclude(&[&["DEFAULT"], &BACK_CHARACTERS[..]].concat(), false) {
+            Ok(response) => response,
+            Err(0) => return Some(None),
+            Err(_) => return None,
+        };
+
+        match format::try_hhmmss_to_dur(&response) {
+            Some(timeout) if !timeout.is_zero() => return Some(Some(timeout)),
+            _ => println!("'{response}' isn't an HH:MM:SS duration longer than zero, try again."),
+        }
+    }
+}
@@ -1,4 +1,9 @@
 pub mod create_schedule;
+pub mod hooks;
 pub mod start_schedule;
 pub mod modify_schedule;
-pub mod modify_app;
\ No newline at end of file
+pub mod modify_app;
+pub mod resume_session;
+pub mod sounds;
+pub mod view_history;
+pub mod view_stats;
\ No newline at end of file
@@ -1,31 +1,32 @@
 use std::path::Path;
 
-use crate::app::{console, AppData, BACK_CHARACTERS, EXPECT_VERIFIED};
+use crate::{app::{console, AppData, BACK_CHARACTERS, EXPECT_VERIFIED}, schedule::{format, hooks::HookSet, sleep::SleepPolicy}, sound};
+use super::{hooks, sounds};
 
-const APP_PROMPTS: [&str; 1] = [
-    "Change the app's audio"
+const APP_PROMPTS: [&str; 8] = [
+    "Change the app's audio",
+    "Turn desktop notifications on or off",
+    "Change the players used to play sounds",
+    "Change the sound for each transition, the volume or how many times sounds play",
+    "Change the warnings before a block ends",
+    "Change what happens when the computer goes to sleep part way through a block",
+    "Change the commands run when a block starts or the schedule ends",
+    "Change how long those commands can run for",
 ];
 
 fn prompt_change_audio() -> Option<Option<String>> {
-    let response;
+    let r = console::get_input_trimmed_exclude(&[&["NONE"], &BACK_CHARACTERS[..]].concat(), false);
 
-    loop {
-        let r = console::get_input_trimmed_exclude(&[&["NONE"], &BACK_CHARACTERS[..]].concat(), false);
-
-        match r {
-            Ok(res) => {
-                response = res;
-                break;
-            }
-            Err(i) => {
-                if i == 0 {
-                    return Some(None);
-                } else {
-                    return None;
-                }
+    let response = match r {
+        Ok(res) => res,
+        Err(i) => {
+            if i == 0 {
+                return Some(None);
+            } else {
+                return None;
             }
         }
-    }
+    };
 
     let path = Path::new(&response);
 
@@ -56,17 +57,18 @@ fn prompt(app_data: &mut AppData) {
             match response.as_ref() {
                 "0" => {
                     println!("Please type the global path to the sound you want to play when an alarm ends.");
+                    println!("It's played for every transition that doesn't have a sound of its own.");
                     println!("If you no longer want to play a sound when an alarm ends, type the word NONE.");
 
                     loop {
                         match prompt_change_audio() {
-                            Some(Some(path)) => {
-                                app_data.set_sound_path(Some(path));
-                                println!("Successfully changed path. The sound at that path will be played whenever an alarm ends.");
-                            }
-                            Some(None) => {
-                                app_data.set_sound_path(None);
-                                println!("Succesfully changed path. A sound will no longer play when an alarm ends.")
+                            Some(Some(path)) => match app_data.set_sound_path(Some(path)) {
+                                Ok(()) => println!("Successfully changed path. The sound at that path will be played whenever an alarm ends."),
+                                Err(e) => println!("Failed to save the new path: {e}"),
+                            },
+                            Some(None) => match app_data.set_sound_path(None) {
+                                Ok(()) => println!("Succesfully changed path. A sound will no longer play when an alarm ends."),
+                                Err(e) => println!("Failed to save the new path: {e}"),
                             },
                             None => {
                                 println!("Path not found, please try again");
@@ -77,6 +79,86 @@ fn prompt(app_data: &mut AppData) {
                         break;
                     }
                 }
+                "1" => {
+                    let enabled = !app_data.notifications_enabled();
+
+                    match app_data.set_notifications(enabled) {
+                        Ok(()) if enabled => println!("Desktop notifications are now on. You'll get one whenever a block starts."),
+                        Ok(()) => println!("Desktop notifications are now off."),
+                        Err(e) => println!("Failed to save the setting: {e}"),
+                    }
+                }
+                "2" => {
+                    println!("Sounds are played by the first of these that works: {}", app_data.sound_players().join(", "));
+                    println!("Type the commands to try instead, separated by commas, or DEFAULT to go back to the usual ones.");
+
+                    loop {
+                        let players = match console::get_input_trimmed_exclude(&[&["DEFAULT"], &BACK_CHARACTERS[..]].concat(), false) {
+                            Ok(list) => match sound::parse_players(&list) {
+                                Some(players) => Some(players),
+                                None => {
+                                    println!("Please type at least one command");
+                                    continue;
+                                }
+                            },
+                            Err(0) => None,
+                            Err(_) => break,
+                        };
+
+                        match app_data.set_sound_players(players) {
+                            Ok(()) => println!("Sounds will now fall back on {}.", app_data.sound_players().join(", ")),
+                            Err(e) => println!("Failed to save the players: {e}"),
+                        }
+
+                        break;
+                    }
+                }
+                "3" => if let Some(new_sounds) = sounds::prompt(app_data.sounds(), &app_data.sound_defaults(), "default") {
+                    match app_data.set_sounds(new_sounds) {
+                        Ok(()) => println!("Successfully changed the sounds."),
+                        Err(e) => println!("Failed to save the sounds: {e}"),
+                    }
+                },
+                "4" => if let Some(warnings) = sounds::prompt_warnings(Some(app_data.warnings()), &[], "default") {
+                    match app_data.set_warnings(warnings.unwrap_or_default()) {
+                        Ok(()) => println!("Successfully changed the warnings."),
+                        Err(e) => println!("Failed to save the warnings: {e}"),
+                    }
+                },
+                "5" => {
+                    println!("When the computer wakes up, {}.", app_data.on_sleep().describe());
+                    println!("What should happen instead?");
+
+                    for (i, policy) in SleepPolicy::ALL.iter().enumerate() {
+                        println!("{i}: {}", policy.describe());
+                    }
+
+                    while let Ok(response) = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false) {
+                        let Some(&policy) = response.parse::<usize>().ok().and_then(|i| SleepPolicy::ALL.get(i)) else {
+                            println!("'{response}' is not a valid response, try again.");
+                            continue;
+                        };
+
+                        match app_data.set_on_sleep(policy) {
+                            Ok(()) => println!("When the computer wakes up, {}.", policy.describe()),
+                            Err(e) => println!("Failed to save the setting: {e}"),
+                        }
+
+                        break;
+                    }
+                }
+                "6" => if let Some(new_hooks) = hooks::prompt(app_data.hooks(), &HookSet::default(), "default") {
+                    match app_data.set_hooks(new_hooks) {
+                        Ok(()) => println!("Successfully changed the commands."),
+                        Err(e) => println!("Failed to save the commands: {e}"),
+                    }
+                },
+                "7" => if let Some(timeout) = hooks::prompt_timeout(app_data.hook_timeout()) {
+                    match app_data.set_hook_timeout(timeout) {
+                        Ok(()) => println!("Commands will be stopped if they run for longer than {}.", format::dur_to_xhxmxs(app_data.hook_timeout())),
+                        Err(e) => println!("Failed to save the setting: {e}"),
+                    }
+                },
                 _ => {
                     println!("'{response}' is not a valid response.");
                 }
@@ -1,14 +1,24 @@
-use crate::{app::{console, AppData, EXPECT_VERIFIED, BACK_CHARACTERS, B_FOR_BACK}, schedule::{Schedule, RepeatType::*, RestType::{*, self}, format::try_hhmmss_to_dur}};
+use std::path::PathBuf;
 
-const CHANGE_OPTIONS: [&str; 6] = [
+use crate::{app::{console, schedule_share::{ConflictChoice, ShareFormat}, AppData, EXPECT_VERIFIED, BACK_CHARACTERS, B_FOR_BACK}, schedule::{Schedule, RepeatType::*, RestType::{*, self}, format::try_hhmmss_to_dur, phase}};
+use super::{hooks, sounds};
+
+const CHANGE_OPTIONS: [&str; 11] = [
     "Name",
     "Work Duration",
     "Rest Duration",
     "Repeat Type",
     "Rest Type",
+    "Phases",
+    "Sounds",
+    "Warnings before a block ends",
+    "Commands run when a block starts or the schedule ends",
+    "Export to a file",
     "Delete this schedule",
 ];
 
+const EXPORT_OPTION: usize = 9;
+
 fn prompt_create_long_rest() -> Option<RestType> {
     let blocks_per_long_rest;
     let long_rest_duration;
@@ -30,20 +40,20 @@ fn prompt_create_long_rest() -> Option<RestType> {
     
         let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
 
-        let dur = try_hhmmss_to_dur(&response);
+        let dur = try_hhmmss_to_dur(&response).filter(|dur| !dur.is_zero());
         
         if let Some(d) = dur {
             long_rest_duration = d;
             break;
         } else {
-            println!("That isn't a valid duration, try again.")
+            println!("That isn't a valid duration longer than zero, try again.")
         }
     }
 
     Some(LongRest { blocks_per_long_rest, long_rest_duration })
 }
 
-fn change_schedule(schedule: &Schedule, option_index: usize) -> Option<Option<Schedule>> {
+fn change_schedule(schedule: &Schedule, option_index: usize, app_data: &AppData) -> Option<Option<Schedule>> {
     let mut new_schedule = schedule.clone();
 
     if option_index == 0 {
@@ -60,12 +70,12 @@ fn change_schedule(schedule: &Schedule, option_index: usize) -> Option<Option<Sc
         loop {
             let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
 
-            if let Some(dur) = try_hhmmss_to_dur(&response) {
+            if let Some(dur) = try_hhmmss_to_dur(&response).filter(|dur| !dur.is_zero()) {
                 new_schedule.work_duration = dur;
                 println!("Successfully changed work duration.");
                 break;
             } else {
-                println!("Could not convert '{response}' to a valid duration, please enter a valid HH:MM:SS duration below");
+                println!("Could not convert '{response}' to a valid duration, please enter an HH:MM:SS duration longer than zero below");
             }
         }
     } else if option_index == 2 {
@@ -74,12 +84,12 @@ fn change_schedule(schedule: &Schedule, option_index: usize) -> Option<Option<Sc
         loop {
             let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
 
-            if let Some(dur) = try_hhmmss_to_dur(&response) {
-                new_schedule.work_duration = dur;
+            if let Some(dur) = try_hhmmss_to_dur(&response).filter(|dur| !dur.is_zero()) {
+                new_schedule.rest_duration = dur;
                 println!("Successfully changed rest duration.");
                 break;
             } else {
-                println!("Could not convert '{response}' to a valid duration, please enter a valid HH:MM:SS duration below");
+                println!("Could not convert '{response}' to a valid duration, please enter an HH:MM:SS duration longer than zero below");
             }
         }
     } else if option_index == 3 {
@@ -208,6 +218,38 @@ fn change_schedule(schedule: &Schedule, option_index: usize) -> Option<Option<Sc
             }
         }
     } else if option_index == 5 {
+        println!("Currently this schedule runs: {}", phase::to_spec(&schedule.phases()));
+        println!("What phases should it run instead? List them like 'warm-up 10:00 rest, 3x(work 50:00, break 10:00 rest), review 15:00',");
+        println!("using * instead of a number to repeat forever, or type NONE to go back to plain work and rest blocks.");
+
+        loop {
+            let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
+
+            if response.eq_ignore_ascii_case("none") {
+                new_schedule.phases = None;
+                println!("Successfully changed back to work and rest blocks.");
+                break;
+            }
+
+            match phase::parse(&response) {
+                Ok(phases) => {
+                    new_schedule.phases = Some(phases);
+                    println!("Successfully changed phases.");
+                    break;
+                }
+                Err(e) => println!("Could not read those phases: {e}. Please try again below"),
+            }
+        }
+    } else if option_index == 6 {
+        new_schedule.sounds = sounds::prompt(&schedule.sounds, &app_data.schedule_sound_defaults(), "app's setting")?;
+        println!("Successfully changed sounds.");
+    } else if option_index == 7 {
+        new_schedule.warnings = sounds::prompt_warnings(schedule.warnings.as_deref(), app_data.warnings(), "app's setting")?;
+        println!("Successfully changed warnings.");
+    } else if option_index == 8 {
+        new_schedule.hooks = hooks::prompt(&schedule.hooks, app_data.hooks(), "app's setting")?;
+        println!("Successfully changed commands.");
+    } else if option_index == 10 {
         loop {
             println!("Are you sure you want to delete {}? (input yes to confirm)", schedule.name);
 
@@ -222,11 +264,93 @@ fn change_schedule(schedule: &Schedule, option_index: usize) -> Option<Option<Sc
     Some(Some(new_schedule))
 }
 
+fn prompt_path(action: &str) -> Option<(PathBuf, ShareFormat)> {
+    println!("Which file would you like to {action}? Files ending in .toml are TOML, anything else is JSON.");
+
+    loop {
+        let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
+
+        if response.is_empty() {
+            println!("Please enter a file path.");
+            continue;
+        }
+
+        let path = PathBuf::from(response);
+        let format = ShareFormat::from_path(&path);
+
+        return Some((path, format));
+    }
+}
+
+fn prompt_export(app_data: &AppData, indices: &[usize]) {
+    let Some((path, format)) = prompt_path("export to") else {
+        return;
+    };
+
+    if path.exists() {
+        println!("{} already exists, would you like to replace it? (yes to confirm)", path.display());
+
+        if console::yes_or_no() != Some(true) {
+            return;
+        }
+    }
+
+    match app_data.export_schedules(indices, &path, format) {
+        Ok(()) => println!("Successfully exported to {}.", path.display()),
+        Err(e) => println!("Failed to export: {e}"),
+    }
+}
+
+fn prompt_conflict(schedule: &Schedule) -> ConflictChoice {
+    println!("There's already a schedule called {}. What would you like to do with the one being imported?", schedule.name);
+    println!("0: Import it under a different name");
+    println!("1: Overwrite the existing schedule");
+    println!("2: Skip it");
+
+    loop {
+        let Ok(response) = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false) else {
+            return ConflictChoice::Skip;
+        };
+
+        match response.as_ref() {
+            "0" => {
+                println!("What should it be called instead?");
+
+                match console::get_input_trimmed_exclude(&BACK_CHARACTERS, false) {
+                    Ok(name) if !name.is_empty() => return ConflictChoice::Rename(name),
+                    _ => return ConflictChoice::Skip,
+                }
+            }
+            "1" => return ConflictChoice::Overwrite,
+            "2" => return ConflictChoice::Skip,
+            _ => println!("Invalid response, please input '0', '1' or '2'"),
+        }
+    }
+}
+
+fn prompt_import(app_data: &mut AppData) {
+    let Some((path, format)) = prompt_path("import") else {
+        return;
+    };
+
+    match app_data.import_schedules(&path, format, prompt_conflict) {
+        Ok(summary) => println!("Imported {} schedules ({} new, {} overwritten), skipped {}.",
+            summary.added.len() + summary.overwritten.len(),
+            summary.added.len(),
+            summary.overwritten.len(),
+            summary.skipped.len(),
+        ),
+        Err(e) => println!("Failed to import: {e}"),
+    }
+}
+
 fn prompt(app_data: &mut AppData) {
     'main: loop {
         println!("Which schedule would you like to modify?");   
 
         app_data.display_schedule_list();
+        println!("Or type IMPORT to import schedules from a file, or EXPORT to export all of them to one.");
+
         let schedule_index;
         loop {
             let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false); 
@@ -237,6 +361,16 @@ fn prompt(app_data: &mut AppData) {
 
             let response = response.expect(EXPECT_VERIFIED);    
 
+            if response.eq_ignore_ascii_case("import") {
+                prompt_import(app_data);
+                continue 'main;
+            }
+
+            if response.eq_ignore_ascii_case("export") {
+                prompt_export(app_data, &(0..app_data.num_schedules()).collect::<Vec<usize>>());
+                continue 'main;
+            }
+
             schedule_index = match response.parse::<usize>() {
                 Ok(num) if num < app_data.num_schedules() => num,
                 _ => {
@@ -266,16 +400,17 @@ fn prompt(app_data: &mut AppData) {
                 let response = response.expect(EXPECT_VERIFIED);
     
                 match response.parse::<usize>() {
+                    Ok(EXPORT_OPTION) => prompt_export(app_data, &[schedule_index]),
                     Ok(option_index) if option_index < CHANGE_OPTIONS.len() => {
-                        match change_schedule(schedule, option_index) {
-                            Some(Some(replacement)) => {
-                                app_data.replace_schedule(schedule_index, replacement);
-                                println!("Successfully updated schedule.");
-                            }
-                            Some(None) => {
-                                app_data.remove_schedule(schedule_index);
-                                println!("Successfully removed schedule.");
-                            }
+                        match change_schedule(schedule, option_index, app_data) {
+                            Some(Some(replacement)) => match app_data.replace_schedule(schedule_index, replacement) {
+                                Ok(()) => println!("Successfully updated schedule."),
+                                Err(e) => println!("Failed to save the updated schedule: {e}"),
+                            },
+                            Some(None) => match app_data.remove_schedule(schedule_index) {
+                                Ok(()) => println!("Successfully removed schedule."),
+                                Err(e) => println!("Failed to remove the schedule: {e}"),
+                            },
                             None => {
                                 continue 'question;
                             },
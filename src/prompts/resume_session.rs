@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use chrono::Local;
+
+use crate::{app::{console, AppData}, schedule::{checkpoint::Checkpoint, format, runner::{self, CaughtUp, Outcome, RunSummary}}};
+
+/// Asks until they answer y or n.
+fn ask(question: &str) -> bool {
+    println!("{question} (y/n)");
+
+    loop {
+        match console::yes_or_no() {
+            Some(answer) => return answer,
+            None => println!("Please type y or n."),
+        }
+    }
+}
+
+fn report(result: Result<(), impl std::fmt::Display>) {
+    if let Err(e) = result {
+        println!("Failed to save this session: {e}");
+        println!("Press enter to continue");
+        console::get_input();
+    }
+}
+
+/// Puts the unfinished session in the history as it was when last saved, as it won't be resumed.
+fn abandon(app_data: &AppData, checkpoint: &Checkpoint) {
+    let summary = RunSummary {
+        outcome: Outcome::Aborted,
+        work_blocks_completed: checkpoint.progress.work_blocks_completed,
+        focused: checkpoint.progress.focused,
+    };
+
+    report(app_data.end_checkpoint(checkpoint, summary, checkpoint.saved_at));
+}
+
+/// Offers to carry on with the session the app was closed part way through, if there was one.
+pub fn offer(app_data: &mut AppData) {
+    let Some(checkpoint) = app_data.take_checkpoint() else {
+        return;
+    };
+
+    //Not `find_schedule`, which would take a name like "2" for an index or match a different schedule's name in another case
+    let index = app_data.find_schedule_by_name(&checkpoint.schedule_name)
+        .filter(|&index| checkpoint.block(app_data.get_schedule(index)).is_some());
+
+    let Some(index) = index else {
+        println!("automato-p was closed part way through {}, but it's been changed or deleted since, so it can't be resumed.", checkpoint.schedule_name);
+        println!("Press enter to continue");
+        console::get_input();
+
+        abandon(app_data, &checkpoint);
+        return;
+    };
+
+    let schedule = app_data.get_schedule(index);
+
+    println!("automato-p was closed part way through {} at {}, in {}.",
+        schedule.name, checkpoint.saved_at.format("%Y-%m-%d %H:%M"), checkpoint.describe(schedule));
+
+    if !ask("Would you like to resume where you left off?") {
+        abandon(app_data, &checkpoint);
+        return;
+    }
+
+    let away = (Local::now() - checkpoint.saved_at).to_std().unwrap_or_default();
+    let mut progress = checkpoint.progress.clone();
+
+    //Paused sessions wouldn't have moved on however long they were left
+    if !progress.paused && away >= Duration::from_secs(1)
+        && ask(&format!("It's been {} since then. Should that time count, as if the timer had kept running?", format::dur_to_xhxmxs(away)))
+    {
+        match runner::catch_up(schedule, &progress, away) {
+            Some(CaughtUp::Running(caught_up)) => progress = caught_up,
+            Some(CaughtUp::Finished { summary, after }) => {
+                println!("{} would have finished by now, so it's been added to your history as finished.", schedule.name);
+                println!("Press enter to continue");
+                console::get_input();
+
+                let ended_at = checkpoint.saved_at + chrono::Duration::from_std(after).unwrap_or_default();
+                report(app_data.end_checkpoint(&checkpoint, summary, ended_at));
+                return;
+            }
+            None => (),
+        }
+    }
+
+    report(app_data.resume_schedule(index, &checkpoint, &progress));
+}
@@ -0,0 +1,120 @@
+use std::{path::Path, time::Duration};
+
+use crate::{app::{console, BACK_CHARACTERS}, schedule::warning, sound::cues::{Cue, CueSound, SoundSet, MAX_REPEAT}};
+
+fn describe_sound(sound: Option<&CueSound>) -> String {
+    match sound {
+        Some(CueSound::File(path)) => path.clone(),
+        Some(CueSound::Silent) | None => String::from("silent"),
+    }
+}
+
+/// What's set, or what's used instead followed by where that comes from.
+fn describe_setting(set: Option<String>, fallback: String, source: &str) -> String {
+    set.unwrap_or_else(|| format!("{fallback} ({source})"))
+}
+
+/// Reads a number up to `max` for one of the settings, with None meaning they typed DEFAULT.
+/// Returns None altogether if they went back.
+fn prompt_number(min: u32, max: u32) -> Option<Option<u32>> {
+    loop {
+        let response = match console::get_input_trimmed_exclude(&[&["DEFAULT"], &BACK_CHARACTERS[..]].concat(), false) {
+            Ok(response) => response,
+            Err(0) => return Some(None),
+            Err(_) => return None,
+        };
+
+        match response.parse::<u32>() {
+            Ok(number) if (min..=max).contains(&number) => return Some(Some(number)),
+            _ => println!("'{response}' isn't a number from {min} to {max}, try again."),
+        }
+    }
+}
+
+fn prompt_cue(sounds: &mut SoundSet, cue: Cue, source: &str) -> Option<()> {
+    println!("Type the global path to the sound to play when {}, SILENT to play nothing, or DEFAULT to use the {source}.", cue.describe());
+
+    loop {
+        match console::get_input_trimmed_exclude(&[&["SILENT", "DEFAULT"], &BACK_CHARACTERS[..]].concat(), false) {
+            Ok(path) if Path::new(&path).exists() => sounds.set(cue, Some(CueSound::File(path))),
+            Ok(_) => {
+                println!("Path not found, please try again");
+                continue;
+            }
+            Err(0) => sounds.set(cue, Some(CueSound::Silent)),
+            Err(1) => sounds.set(cue, None),
+            Err(_) => return None,
+        }
+
+        return Some(());
+    }
+}
+
+/// Lets one of the sounds in `sounds`, or their volume or repeat count, be changed.
+/// Anything `sounds` doesn't set comes from `fallback`, and `source` says where that is, like "app's setting".
+/// Returns None if they backed out without changing anything.
+pub fn prompt(sounds: &SoundSet, fallback: &SoundSet, source: &str) -> Option<SoundSet> {
+    let mut new_sounds = sounds.clone();
+    let defaults = fallback.playback();
+
+    println!("What would you like to change about the sounds?");
+
+    for (i, cue) in Cue::ALL.iter().enumerate() {
+        println!("{i}: The sound played when {} (currently {})", cue.describe(),
+            describe_setting(sounds.get(*cue).map(|sound| describe_sound(Some(sound))), describe_sound(fallback.get(*cue)), source));
+    }
+
+    println!("{}: The volume (currently {})", Cue::ALL.len(),
+        describe_setting(sounds.volume.map(|volume| format!("{volume}%")), format!("{}%", defaults.volume), source));
+    println!("{}: How many times each sound plays (currently {})", Cue::ALL.len() + 1,
+        describe_setting(sounds.repeat.map(|repeat| repeat.to_string()), defaults.repeat.to_string(), source));
+
+    loop {
+        let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).ok()?;
+
+        match response.parse::<usize>() {
+            Ok(i) if i < Cue::ALL.len() => prompt_cue(&mut new_sounds, Cue::ALL[i], source)?,
+            Ok(i) if i == Cue::ALL.len() => {
+                println!("Type a volume from 0 to 100, or DEFAULT to use the {source}.");
+                new_sounds.volume = prompt_number(0, 100)?.map(|volume| volume as u8);
+            }
+            Ok(i) if i == Cue::ALL.len() + 1 => {
+                println!("Type how many times each sound should play, from 1 to {MAX_REPEAT}, or DEFAULT to use the {source}.");
+                new_sounds.repeat = prompt_number(1, MAX_REPEAT)?;
+            }
+            _ => {
+                println!("'{response}' is not a valid response, try again.");
+                continue;
+            }
+        }
+
+        return Some(new_sounds);
+    }
+}
+
+/// Asks how long before the end of each block to warn that it's ending. `current` is None if it comes from `source`.
+/// Returns None if they went back, or Some(None) if they typed DEFAULT.
+pub fn prompt_warnings(current: Option<&[Duration]>, fallback: &[Duration], source: &str) -> Option<Option<Vec<Duration>>> {
+    match current {
+        Some(warnings) => println!("Currently: {}", warning::describe(warnings)),
+        None => println!("Currently ({source}): {}", warning::describe(fallback)),
+    }
+
+    println!("How long before the end should you be warned instead? List the times like '1:00, 10' (one minute and ten seconds before),");
+    println!("type NONE to not be warned, or DEFAULT to use the {source}.");
+
+    loop {
+        let response = match console::get_input_trimmed_exclude(&[&["NONE", "DEFAULT"], &BACK_CHARACTERS[..]].concat(), false) {
+            Ok(response) => response,
+            Err(0) => return Some(Some(Vec::new())),
+            Err(1) => return Some(None),
+            Err(_) => return None,
+        };
+
+        match warning::parse(&response) {
+            Ok(warnings) if !warnings.is_empty() => return Some(Some(warnings)),
+            Ok(_) => println!("Please list at least one time, or type NONE."),
+            Err(e) => println!("{e}, please try again."),
+        }
+    }
+}
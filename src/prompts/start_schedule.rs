@@ -1,21 +1,139 @@
-use crate::app::{console, AppData, EXPECT_VERIFIED, BACK_CHARACTERS};
+use std::fmt::Write;
+
+use chrono::{DateTime, Local};
+
+use crate::{app::{console, AppData, EXPECT_VERIFIED, BACK_CHARACTERS, B_FOR_BACK}, schedule::{format, plan, runner::BlockKind, Schedule}};
+
+/// How many work blocks of an infinite schedule the preview shows.
+const PREVIEW_WORK_BLOCKS: u32 = 8;
+/// How many blocks of an infinite schedule the preview shows at most, for ones that take a long time to get to their
+/// work blocks (or never do, like ones made only of rests).
+const PREVIEW_MAX_BLOCKS: usize = 32;
+
+/// Lists every block with when it would start and end if the schedule were started at `now`.
+fn timeline(schedule: &Schedule, now: DateTime<Local>) -> String {
+    let (mut planned, infinite) = match plan::plan(schedule, now, None) {
+        Some(planned) => (planned, false),
+        None => (plan::plan(schedule, now, Some(PREVIEW_WORK_BLOCKS)).expect(EXPECT_VERIFIED), true),
+    };
+
+    let cut_short = infinite && planned.len() > PREVIEW_MAX_BLOCKS;
+    planned.truncate(if cut_short {PREVIEW_MAX_BLOCKS} else {planned.len()});
+
+    let Some(last) = planned.last() else {
+        return format!("{} doesn't have any blocks to run.\n", schedule.name);
+    };
+
+    //Only bother with days if the session goes past midnight
+    let time_format = if last.end.date_naive() == now.date_naive() {"%H:%M"} else {"%a %H:%M"};
+
+    let mut out = format!("{schedule}\n\nIf started now:\n");
+
+    for planned in &planned {
+        writeln!(out, "  {} - {}  {:<32} {:>8}{}",
+            planned.start.format(time_format),
+            planned.end.format(time_format),
+            planned.block.name(),
+            format::dur_to_xhxmxs(planned.block.duration),
+            //Phases can call their long rests anything, so they're pointed out
+            if planned.block.kind == BlockKind::LongRest && planned.block.label != "Long rest" {"  <- long rest"} else {""},
+        ).ok();
+    }
+
+    out.push('\n');
+
+    if cut_short {
+        writeln!(out, "...and so on until you quit.").ok();
+    } else if infinite {
+        writeln!(out, "...and so on until you quit. {PREVIEW_WORK_BLOCKS} work blocks would take until {}.", last.end.format(time_format)).ok();
+    } else {
+        let total = (last.end - now).to_std().unwrap_or_default();
+        writeln!(out, "Finishes at {} ({}).", last.end.format(time_format), format::dur_to_xhxmxs(total)).ok();
+    }
+
+    out
+}
+
+/// Shows the timeline and asks whether to go ahead, returning false if they'd rather pick another schedule.
+fn confirm_start(schedule: &Schedule) -> bool {
+    console::clear();
+    print!("{}", timeline(schedule, Local::now()));
+
+    println!();
+    println!("Press enter to start, or type BACK to choose a different schedule.");
+
+    console::get_input_trimmed_exclude(&BACK_CHARACTERS, false).is_ok()
+}
 
 pub fn start(app_data: &mut AppData) {
-    println!("Which schedule would you like to start?");
-    app_data.display_schedule_list();
+    'choose: loop {
+        println!("{B_FOR_BACK}");
+        println!("Which schedule would you like to start?");
+        app_data.display_schedule_list();
 
-    loop {
-        let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false);
+        loop {
+            let response = console::get_input_trimmed_exclude(&BACK_CHARACTERS, false);
 
-        if response.is_err() {
-            return;
-        }
+            if response.is_err() {
+                return;
+            }
+
+            let response = response.expect(EXPECT_VERIFIED);
+
+            match response.parse::<usize>() {
+                Ok(index) if index < app_data.num_schedules() => {
+                    if !confirm_start(app_data.get_schedule(index)) {
+                        console::clear();
+                        continue 'choose;
+                    }
 
-        let response = response.expect(EXPECT_VERIFIED);
+                    if let Err(e) = app_data.start_schedule(index) {
+                        println!("Failed to save this session: {e}");
+                        println!("Press enter to continue");
+                        console::get_input();
+                    }
 
-        match response.parse::<usize>() {
-            Ok(index) if index < app_data.num_schedules() => app_data.start_schedule(index),
-            _ => println!("{response} is an invalid response, try again"),
+                    return;
+                }
+                _ => println!("{response} is an invalid response, try again"),
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::schedule::phase;
+
+    fn nine_am() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn finite_schedules_should_list_every_block() {
+        let schedule = Schedule::from_phases(String::from("Day"), phase::parse("warm-up 10:00 rest, 2x(work 50:00, break 10:00 rest), lunch 1:00:00 long-rest").unwrap());
+        let timeline = timeline(&schedule, nine_am());
+        let lines: Vec<&str> = timeline.lines().collect();
+
+        assert_eq!(lines[2], "If started now:");
+        assert!(lines[3].starts_with("  09:00 - 09:10  warm-up"), "{timeline}");
+        assert!(lines[8].starts_with("  11:10 - 12:10  lunch") && lines[8].ends_with("<- long rest"), "{timeline}");
+        assert_eq!(lines.last(), Some(&"Finishes at 12:10 (3h10m)."));
+    }
+
+    #[test]
+    fn infinite_schedules_should_only_preview_so_many_lines() {
+        let pomodoro = Schedule::from_phases(String::from("Forever"), phase::parse("*(work 25:00, rest 5:00 rest)").unwrap());
+        let timeline_lines = timeline(&pomodoro, nine_am()).lines().count();
+        assert_eq!(timeline_lines, 3 + 15 + 2, "8 work blocks and the rests between them");
+
+        let rests = Schedule::from_phases(String::from("Lazy"), phase::parse("*(nap 5:00 rest)").unwrap());
+        let timeline = timeline(&rests, nine_am());
+
+        assert_eq!(timeline.lines().count(), 3 + PREVIEW_MAX_BLOCKS + 2, "{timeline}");
+        assert!(timeline.ends_with("\n...and so on until you quit.\n"), "{timeline}");
+    }
+}
@@ -0,0 +1,18 @@
+use crate::{app::{console, AppData}, history};
+
+pub fn start(app_data: &mut AppData) {
+    match app_data.read_history() {
+        Ok(loaded) => {
+            history::display_history(&loaded.entries);
+
+            if !loaded.bad_lines.is_empty() {
+                println!("\n{} entries couldn't be read and were left out:", loaded.bad_lines.len());
+                loaded.bad_lines.iter().for_each(|bad| println!("{}", bad.error));
+            }
+        }
+        Err(e) => println!("Failed to read your history: {e}"),
+    }
+
+    println!("\nPress enter to return to the main menu");
+    console::get_input();
+}
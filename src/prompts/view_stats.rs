@@ -0,0 +1,19 @@
+use chrono::Local;
+
+use crate::{app::{console, AppData}, history::stats::{ReportFormat, Stats}};
+
+pub fn start(app_data: &mut AppData) {
+    match app_data.read_history() {
+        Ok(loaded) => {
+            print!("{}", Stats::from_entries(&loaded.entries, Local::now().date_naive()).render(ReportFormat::Table));
+
+            if !loaded.bad_lines.is_empty() {
+                println!("\n{} history entries couldn't be read and were left out.", loaded.bad_lines.len());
+            }
+        }
+        Err(e) => println!("Failed to read your history: {e}"),
+    }
+
+    println!("\nPress enter to return to the main menu");
+    console::get_input();
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, TimeZone, Utc};
+
+use super::{plan::PlannedBlock, runner::BlockKind};
+
+/// iCalendar lines can't be longer than this many bytes, not counting the line ending.
+const MAX_LINE_OCTETS: usize = 75;
+
+fn utc_timestamp<Tz: TimeZone>(time: &DateTime<Tz>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Splits a content line over several lines once it gets too long, never in the middle of a character.
+fn fold_line(line: &str, out: &mut String) {
+    let mut len = 0;
+
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_OCTETS {
+            //Continuation lines start with a space, which counts towards their length
+            out.push_str("\r\n ");
+            len = 1;
+        }
+
+        out.push(c);
+        len += c.len_utf8();
+    }
+
+    out.push_str("\r\n");
+}
+
+/// An iCalendar file with one event per block, so the session can be blocked out in a calendar.
+/// Work blocks show as busy and rests as free. `created` is used for timestamps and to keep event IDs unique between exports.
+pub fn to_ics<Tz: TimeZone>(schedule_name: &str, blocks: &[PlannedBlock<Tz>], created: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    let stamp = utc_timestamp(&created);
+
+    let mut line = |line: String| fold_line(&line, &mut out);
+
+    line(String::from("BEGIN:VCALENDAR"));
+    line(String::from("VERSION:2.0"));
+    line(String::from("PRODID:-//automato-p//automato-p//EN"));
+    line(String::from("CALSCALE:GREGORIAN"));
+
+    for (i, planned) in blocks.iter().enumerate() {
+        let (category, transparency) = match planned.block.kind {
+            BlockKind::Work => ("WORK", "OPAQUE"),
+            BlockKind::Rest => ("REST", "TRANSPARENT"),
+            BlockKind::LongRest => ("LONG REST", "TRANSPARENT"),
+        };
+
+        line(String::from("BEGIN:VEVENT"));
+        line(format!("UID:{stamp}-{i}@automato-p"));
+        line(format!("DTSTAMP:{stamp}"));
+        line(format!("DTSTART:{}", utc_timestamp(&planned.start)));
+        line(format!("DTEND:{}", utc_timestamp(&planned.end)));
+        line(format!("SUMMARY:{}", escape_text(&format!("{} ({schedule_name})", planned.block.name()))));
+        line(format!("CATEGORIES:{category}"));
+        line(format!("TRANSP:{transparency}"));
+        line(String::from("END:VEVENT"));
+    }
+
+    line(String::from("END:VCALENDAR"));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::{plan, Schedule};
+
+    #[test]
+    fn every_block_should_be_an_event_with_short_enough_lines() {
+        let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+        let planned = plan::plan(&Schedule::pomodoro(), start, None).unwrap();
+        let name = "A schedule with a long name, some commas; and semicolons, that needs folding over more than one line";
+
+        let ics = to_ics(name, &planned, start);
+
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 15);
+        assert!(ics.contains("DTSTART:20261019T090000Z\r\nDTEND:20261019T092500Z\r\n"));
+        assert!(ics.contains("SUMMARY:Long rest (A schedule"));
+        assert!(ics.contains("commas\\; and semicolons\\,"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS), "{ics}");
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+
+use super::{format, render::Renderer, runner::{Block, BlockKind, Progress}, Schedule};
+
+/// Where a running session was up to when it was last saved, so it can be picked up again if the app is closed part way through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Schedules are looked up by name, as that's what they're known by everywhere else.
+    pub schedule_name: String,
+    /// The block that was running, to check it's still the same block if the schedule has been changed since.
+    pub kind: BlockKind,
+    pub label: String,
+    #[serde(flatten)]
+    pub progress: Progress,
+    pub started_at: DateTime<Local>,
+    pub saved_at: DateTime<Local>,
+}
+
+impl Checkpoint {
+    /// The block the checkpoint was saved in, or None if `schedule` has changed so much it isn't there anymore.
+    pub fn block(&self, schedule: &Schedule) -> Option<Block> {
+        schedule.blocks().nth(self.progress.position).filter(|block| block.kind == self.kind && block.label == self.label)
+    }
+
+    /// Where the session was up to, like "Work block 3 with 12:34 left".
+    pub fn describe(&self, schedule: &Schedule) -> String {
+        let name = self.block(schedule).map_or_else(|| self.label.clone(), |block| block.name());
+
+        format!("{name} with {} left{}", format::dur_to_hhmmss(self.progress.remaining), if self.progress.paused {" (paused)"} else {""})
+    }
+}
+
+type Save<'a> = Box<dyn FnMut(Option<&Checkpoint>) + 'a>;
+
+/// Hands `save` a checkpoint as the schedule runs, and None once the run is over and there's nothing left to resume.
+pub struct Checkpointer<'a> {
+    schedule_name: String,
+    started_at: DateTime<Local>,
+    save: Save<'a>,
+}
+
+impl<'a> Checkpointer<'a> {
+    /// `started_at` is when the session was first started, which is earlier than now if it's being resumed.
+    pub fn new(schedule_name: &str, started_at: DateTime<Local>, save: impl FnMut(Option<&Checkpoint>) + 'a) -> Checkpointer<'a> {
+        Checkpointer { schedule_name: schedule_name.to_string(), started_at, save: Box::new(save) }
+    }
+}
+
+impl Renderer for Checkpointer<'_> {
+    fn checkpoint(&mut self, block: &Block, progress: &Progress) {
+        (self.save)(Some(&Checkpoint {
+            schedule_name: self.schedule_name.clone(),
+            kind: block.kind,
+            label: block.label.clone(),
+            progress: progress.clone(),
+            started_at: self.started_at,
+            saved_at: Local::now(),
+        }));
+    }
+
+    fn completed(&mut self) {
+        (self.save)(None);
+    }
+
+    fn aborted(&mut self) {
+        (self.save)(None);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{time::{Duration, Instant, SystemTime}, thread};
+
+#[cfg(test)]
+use std::cell::Cell;
+
+/// Where a running schedule gets the time from, and how it waits.
+pub trait Clock {
+    /// Monotonic time, which is what blocks are timed by. It stops while the computer is asleep.
+    fn now(&self) -> Instant;
+
+    /// The real time, which keeps going while the computer is asleep, so sleep can be noticed.
+    fn wall(&self) -> SystemTime;
+
+    fn sleep(&self, dur: Duration);
+}
+
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, dur: Duration) {
+        thread::sleep(dur);
+    }
+}
+
+/// A clock that only moves when told to. Sleeping advances it instantly, so whole schedules can be run in tests without waiting.
+#[cfg(test)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Cell<Duration>,
+    asleep: Cell<Duration>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock {
+            start: Instant::now(),
+            elapsed: Cell::new(Duration::ZERO),
+            asleep: Cell::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, dur: Duration) {
+        self.elapsed.set(self.elapsed.get() + dur);
+    }
+
+    /// Moves the real time on without the monotonic time, like the computer going to sleep.
+    pub fn suspend(&self, dur: Duration) {
+        self.asleep.set(self.asleep.get() + dur);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+}
+
+#[cfg(test)]
+impl Default for ManualClock {
+    fn default() -> ManualClock {
+        ManualClock::new()
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + self.elapsed.get() + self.asleep.get()
+    }
+
+    fn sleep(&self, dur: Duration) {
+        self.advance(dur);
+    }
+}
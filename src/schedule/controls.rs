@@ -0,0 +1,149 @@
+use std::time::Duration;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::console;
+
+pub const CONTROLS_HELP: &str = "[space] pause/resume  [s] skip block  [r] restart block  [e] extend block  [c] count sleep  [q] quit";
+
+pub enum Control {
+    TogglePause,
+    Skip,
+    Restart,
+    Extend(Duration),
+    /// Counts the time the computer was just asleep for, when asked whether it should.
+    CountSleep,
+    Abort,
+}
+
+/// Where a running schedule gets its controls from.
+pub trait Controls {
+    /// Waits at most `timeout` for the next control. This is also how the runner waits between ticks, so it should take the full timeout if nothing happens.
+    fn poll(&mut self, timeout: Duration) -> Option<Control>;
+
+    /// What should be displayed under the timer.
+    fn status_line(&self) -> String {
+        String::new()
+    }
+}
+
+/// Turns key presses into controls for a running schedule.
+/// Extending takes a number of minutes, so the digits typed after pressing 'e' are buffered here until enter is pressed.
+#[derive(Default)]
+pub struct ControlInput {
+    extend_minutes: Option<String>,
+}
+
+impl ControlInput {
+    pub fn new() -> ControlInput {
+        ControlInput { extend_minutes: None }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Control> {
+        //Raw mode swallows ctrl-c, so treat it like quitting
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.extend_minutes = None;
+            return Some(Control::Abort);
+        }
+
+        if let Some(minutes) = &mut self.extend_minutes {
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() => minutes.push(c),
+                KeyCode::Backspace => {
+                    minutes.pop();
+                }
+                KeyCode::Esc => self.extend_minutes = None,
+                KeyCode::Enter => {
+                    let minutes = self.extend_minutes.take()?;
+
+                    return match minutes.parse::<u64>() {
+                        Ok(m) if m > 0 => Some(Control::Extend(Duration::from_secs(m * 60))),
+                        _ => None,
+                    };
+                }
+                _ => (),
+            }
+
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::Char('p') => Some(Control::TogglePause),
+            KeyCode::Char('s') => Some(Control::Skip),
+            KeyCode::Char('r') => Some(Control::Restart),
+            KeyCode::Char('c') => Some(Control::CountSleep),
+            KeyCode::Char('e') => {
+                self.extend_minutes = Some(String::new());
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc => Some(Control::Abort),
+            _ => None,
+        }
+    }
+}
+
+impl Controls for ControlInput {
+    /// Waits at most `timeout` for a key press and returns the control it finished, if any.
+    fn poll(&mut self, timeout: Duration) -> Option<Control> {
+        let key = console::poll_key_press(timeout)?;
+
+        self.handle_key(key)
+    }
+
+    /// The help text, or the extend prompt if it's being typed in.
+    fn status_line(&self) -> String {
+        match &self.extend_minutes {
+            Some(minutes) => format!("Extend block by how many minutes? {minutes}_  ([enter] confirm, [esc] cancel)"),
+            None => String::from(CONTROLS_HELP),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut ControlInput, code: KeyCode) -> Option<Control> {
+        input.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn keys_should_map_to_controls() {
+        let mut input = ControlInput::new();
+
+        assert!(matches!(press(&mut input, KeyCode::Char(' ')), Some(Control::TogglePause)));
+        assert!(matches!(press(&mut input, KeyCode::Char('p')), Some(Control::TogglePause)));
+        assert!(matches!(press(&mut input, KeyCode::Char('s')), Some(Control::Skip)));
+        assert!(matches!(press(&mut input, KeyCode::Char('r')), Some(Control::Restart)));
+        assert!(matches!(press(&mut input, KeyCode::Char('c')), Some(Control::CountSleep)));
+        assert!(matches!(press(&mut input, KeyCode::Char('q')), Some(Control::Abort)));
+        assert!(matches!(press(&mut input, KeyCode::Esc), Some(Control::Abort)));
+        assert!(press(&mut input, KeyCode::Char('x')).is_none());
+        assert!(matches!(input.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), Some(Control::Abort)));
+        assert_eq!(input.status_line(), CONTROLS_HELP);
+    }
+
+    #[test]
+    fn extending_should_buffer_minutes_until_enter() {
+        let mut input = ControlInput::new();
+
+        assert!(press(&mut input, KeyCode::Char('e')).is_none());
+        for code in [KeyCode::Char('1'), KeyCode::Char('x'), KeyCode::Char('5'), KeyCode::Char('7'), KeyCode::Backspace, KeyCode::Char('s')] {
+            assert!(press(&mut input, code).is_none(), "keys should only be typed into the prompt while it's open");
+        }
+        assert!(input.status_line().contains("minutes? 15_"), "{}", input.status_line());
+
+        assert!(matches!(press(&mut input, KeyCode::Enter), Some(Control::Extend(dur)) if dur == Duration::from_secs(15 * 60)));
+        assert_eq!(input.status_line(), CONTROLS_HELP);
+
+        //Nothing, zero and cancelling don't extend anything
+        for keys in [vec![KeyCode::Enter], vec![KeyCode::Char('0'), KeyCode::Enter], vec![KeyCode::Char('5'), KeyCode::Esc]] {
+            press(&mut input, KeyCode::Char('e'));
+            assert!(keys.into_iter().all(|code| press(&mut input, code).is_none()));
+            assert_eq!(input.status_line(), CONTROLS_HELP);
+        }
+
+        press(&mut input, KeyCode::Char('e'));
+        assert!(matches!(input.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), Some(Control::Abort)));
+        assert_eq!(input.status_line(), CONTROLS_HELP, "quitting should close the prompt too");
+    }
+}
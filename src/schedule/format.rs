@@ -75,10 +75,8 @@ pub fn try_hhmmss_to_dur(str: &str) -> Option<Duration> {
 
         let parsed: u64 = s.parse().ok()?;
 
-        if parsed > 60 {
-            if i == 0 || i == 1 {
-                return None
-            }
+        if parsed > 60 && (i == 0 || i == 1) {
+            return None
         }
 
         secs += parsed * 60u64.pow(i);
@@ -0,0 +1,424 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Read},
+    os::unix::process::CommandExt,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Serialize, Deserialize};
+
+use super::{format, render::Renderer, runner::{Block, BlockKind, Tick}};
+
+/// How long a hook can run for before it's stopped, unless the settings say otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a running hook is checked on to see if it's finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How much of what a failed hook wrote to stderr makes it into the error.
+const MAX_STDERR: usize = 200;
+
+/// The moments in a run that can have a command of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    WorkStart,
+    RestStart,
+    LongRestStart,
+    SessionComplete,
+    SessionAbort,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 5] = [HookEvent::WorkStart, HookEvent::RestStart, HookEvent::LongRestStart, HookEvent::SessionComplete, HookEvent::SessionAbort];
+
+    pub fn for_block(kind: BlockKind) -> HookEvent {
+        match kind {
+            BlockKind::Work => HookEvent::WorkStart,
+            BlockKind::Rest => HookEvent::RestStart,
+            BlockKind::LongRest => HookEvent::LongRestStart,
+        }
+    }
+
+    /// What hooks are told the event is, in `$AUTOMATO_P_EVENT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::WorkStart => "work_start",
+            HookEvent::RestStart => "rest_start",
+            HookEvent::LongRestStart => "long_rest_start",
+            HookEvent::SessionComplete => "session_complete",
+            HookEvent::SessionAbort => "session_abort",
+        }
+    }
+
+    /// Finishes the sentence "Run when...".
+    pub fn describe(self) -> &'static str {
+        match self {
+            HookEvent::WorkStart => "work starts",
+            HookEvent::RestStart => "a rest starts",
+            HookEvent::LongRestStart => "a long rest starts",
+            HookEvent::SessionComplete => "the schedule is complete",
+            HookEvent::SessionAbort => "the schedule is quit before it's complete",
+        }
+    }
+
+    /// How the event's hook is named on the command line.
+    pub fn setting_name(self) -> &'static str {
+        match self {
+            HookEvent::WorkStart => "work-start-hook",
+            HookEvent::RestStart => "rest-start-hook",
+            HookEvent::LongRestStart => "long-rest-start-hook",
+            HookEvent::SessionComplete => "session-complete-hook",
+            HookEvent::SessionAbort => "session-abort-hook",
+        }
+    }
+
+    pub fn from_setting_name(name: &str) -> Option<HookEvent> {
+        HookEvent::ALL.into_iter().find(|event| event.setting_name() == name)
+    }
+}
+
+/// The shell command to run for each event. Like sounds, anything left as None falls back on another set,
+/// so schedules only have to set the hooks they want to be different from the app's. An empty command runs nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest_start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_rest_start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_complete: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_abort: Option<String>,
+}
+
+impl HookSet {
+    pub fn is_empty(&self) -> bool {
+        *self == HookSet::default()
+    }
+
+    pub fn get(&self, event: HookEvent) -> Option<&String> {
+        match event {
+            HookEvent::WorkStart => self.work_start.as_ref(),
+            HookEvent::RestStart => self.rest_start.as_ref(),
+            HookEvent::LongRestStart => self.long_rest_start.as_ref(),
+            HookEvent::SessionComplete => self.session_complete.as_ref(),
+            HookEvent::SessionAbort => self.session_abort.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, event: HookEvent, command: Option<String>) {
+        match event {
+            HookEvent::WorkStart => self.work_start = command,
+            HookEvent::RestStart => self.rest_start = command,
+            HookEvent::LongRestStart => self.long_rest_start = command,
+            HookEvent::SessionComplete => self.session_complete = command,
+            HookEvent::SessionAbort => self.session_abort = command,
+        }
+    }
+
+    /// These hooks, with anything they leave out taken from `fallback`.
+    pub fn or(&self, fallback: &HookSet) -> HookSet {
+        let mut hooks = HookSet::default();
+
+        for event in HookEvent::ALL {
+            hooks.set(event, self.get(event).or(fallback.get(event)).cloned());
+        }
+
+        hooks
+    }
+
+    /// The command to run for `event`, if there is one.
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        self.get(event).map(String::as_str).filter(|command| !command.trim().is_empty())
+    }
+}
+
+#[derive(Debug)]
+pub enum HookError {
+    /// The shell couldn't be started, or the hook couldn't be waited on.
+    Run {
+        event: HookEvent,
+        reason: String,
+    },
+    /// The hook exited with an error, with the last of what it wrote to stderr.
+    Failed {
+        event: HookEvent,
+        status: ExitStatus,
+        stderr: String,
+    },
+    /// The hook took longer than it's allowed to, so it was killed.
+    TimedOut {
+        event: HookEvent,
+        timeout: Duration,
+    },
+}
+
+impl Display for HookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookError::Run { event, reason } => write!(f, "the {} hook could not be run: {reason}", event.name()),
+            HookError::Failed { event, status, stderr } if stderr.is_empty() => write!(f, "the {} hook failed with {status}", event.name()),
+            HookError::Failed { event, status, stderr } => write!(f, "the {} hook failed with {status}: {stderr}", event.name()),
+            HookError::TimedOut { event, timeout } => write!(f, "the {} hook was stopped after running for {}", event.name(), format::dur_to_xhxmxs(*timeout)),
+        }
+    }
+}
+
+impl Error for HookError {}
+
+/// The end of `stderr` on one line, as that's usually where the reason it failed is.
+fn last_of(stderr: &str) -> String {
+    let line = stderr.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    match line.char_indices().rev().nth(MAX_STDERR - 1) {
+        Some((start, _)) if start > 0 => format!("...{}", &line[start..]),
+        _ => line,
+    }
+}
+
+/// Runs `command` through `sh` with `env` added to its environment, waiting up to `timeout` for it to finish.
+pub fn run(event: HookEvent, command: &str, env: &[(String, String)], timeout: Duration) -> Result<(), HookError> {
+    let run_error = |e: io::Error| HookError::Run { event, reason: e.to_string() };
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        //A group of its own, so anything it starts can be stopped along with it
+        .process_group(0)
+        .spawn()
+        .map_err(run_error)?;
+
+    //Read separately, so a hook that writes a lot can't fill the pipe up and stall until it's timed out
+    let mut stderr = child.stderr.take().expect("Hooks should be spawned with a pipe for stderr");
+    let (output, read) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut contents = String::new();
+        stderr.read_to_string(&mut contents).ok();
+        output.send(contents).ok();
+    });
+
+    let started = Instant::now();
+
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(run_error)? {
+            break status;
+        }
+
+        if started.elapsed() >= timeout {
+            kill_group(&mut child);
+            return Err(HookError::TimedOut { event, timeout });
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    if status.success() {
+        return Ok(());
+    }
+
+    //Anything the hook left running in the background could keep stderr open, so it isn't waited on for long
+    let stderr = read.recv_timeout(POLL_INTERVAL * 5).unwrap_or_default();
+    Err(HookError::Failed { event, status, stderr: last_of(&stderr) })
+}
+
+/// Kills the hook and everything it started, which would otherwise carry on after it's been stopped. There's no
+/// way to signal a group in std, so it's left to `kill`.
+fn kill_group(child: &mut Child) {
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("kill -s KILL -- -{}", child.id()))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok();
+
+    child.kill().ok();
+    child.wait().ok();
+}
+
+/// A hook waiting to be run, with everything it's told about the event.
+struct Job {
+    event: HookEvent,
+    command: String,
+    env: Vec<(String, String)>,
+}
+
+/// Runs the hooks for each event as the schedule gets to it. Hooks are run one after the other in the background,
+/// so a slow one holds up the hooks after it rather than the timer, and anything that goes wrong is sent to `errors`.
+/// Dropping it waits for the hooks that are left, so the last of them aren't cut off if the app is closing.
+pub struct EventHooks {
+    schedule_name: String,
+    hooks: HookSet,
+    jobs: Option<Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+    /// The block that started last, which the session events are told about as well.
+    block: Option<Block>,
+    elapsed: Duration,
+}
+
+impl EventHooks {
+    pub fn new(schedule_name: &str, hooks: HookSet, timeout: Duration, errors: Sender<HookError>) -> EventHooks {
+        let (jobs, queue) = mpsc::channel::<Job>();
+
+        let worker = thread::spawn(move || {
+            for job in queue {
+                if let Err(e) = run(job.event, &job.command, &job.env, timeout) {
+                    errors.send(e).ok();
+                }
+            }
+        });
+
+        EventHooks {
+            schedule_name: schedule_name.to_string(),
+            hooks,
+            jobs: Some(jobs),
+            worker: Some(worker),
+            block: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    fn env(&self, event: HookEvent) -> Vec<(String, String)> {
+        let mut env = vec![
+            ("AUTOMATO_P_EVENT", event.name().to_string()),
+            ("AUTOMATO_P_SCHEDULE", self.schedule_name.clone()),
+            ("AUTOMATO_P_ELAPSED", self.elapsed.as_secs().to_string()),
+        ];
+
+        if let Some(block) = &self.block {
+            env.extend([
+                ("AUTOMATO_P_BLOCK", block.name()),
+                ("AUTOMATO_P_BLOCK_KIND", String::from(match block.kind {
+                    BlockKind::Work => "work",
+                    BlockKind::Rest => "rest",
+                    BlockKind::LongRest => "long-rest",
+                })),
+                ("AUTOMATO_P_BLOCK_DURATION", block.duration.as_secs().to_string()),
+                ("AUTOMATO_P_WORK_BLOCK", block.index.to_string()),
+            ]);
+        }
+
+        env.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+    }
+
+    fn fire(&mut self, event: HookEvent) {
+        let Some(command) = self.hooks.command(event) else {
+            return;
+        };
+
+        let job = Job { event, command: command.to_string(), env: self.env(event) };
+
+        if let Some(jobs) = &self.jobs {
+            jobs.send(job).ok();
+        }
+    }
+}
+
+impl Renderer for EventHooks {
+    fn block_started(&mut self, block: &Block) {
+        self.block = Some(block.clone());
+        self.fire(HookEvent::for_block(block.kind));
+    }
+
+    fn tick(&mut self, tick: &Tick) {
+        self.elapsed = tick.elapsed;
+    }
+
+    fn completed(&mut self) {
+        self.fire(HookEvent::SessionComplete);
+    }
+
+    fn aborted(&mut self) {
+        self.fire(HookEvent::SessionAbort);
+    }
+}
+
+impl Drop for EventHooks {
+    fn drop(&mut self) {
+        //Closing the queue lets the worker finish once it's run what's already in it
+        self.jobs = None;
+
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn block(kind: BlockKind, index: u32) -> Block {
+        Block { kind, index, duration: Duration::from_secs(1500), label: String::from(if kind == BlockKind::Work {"Work"} else {"Rest"}) }
+    }
+
+    #[test]
+    fn schedule_hooks_should_override_the_apps_and_empty_ones_should_turn_them_off() {
+        let app = HookSet {
+            work_start: Some(String::from("dnd on")),
+            rest_start: Some(String::from("dnd off")),
+            ..HookSet::default()
+        };
+
+        let schedule = HookSet {
+            rest_start: Some(String::new()),
+            session_complete: Some(String::from("notify-send done")),
+            ..HookSet::default()
+        };
+
+        let hooks = schedule.or(&app);
+
+        assert_eq!(hooks.command(HookEvent::WorkStart), Some("dnd on"));
+        assert_eq!(hooks.command(HookEvent::RestStart), None);
+        assert_eq!(hooks.command(HookEvent::SessionComplete), Some("notify-send done"));
+        assert_eq!(hooks.command(HookEvent::SessionAbort), None);
+        assert_eq!(HookEvent::from_setting_name("long-rest-start-hook"), Some(HookEvent::LongRestStart));
+    }
+
+    #[test]
+    fn hooks_should_be_told_about_the_event_and_have_failures_reported() {
+        let dir = TestDir::new("hooks");
+        let log = dir.join("log");
+
+        let hooks = HookSet {
+            work_start: Some(format!("echo \"$AUTOMATO_P_EVENT $AUTOMATO_P_SCHEDULE: $AUTOMATO_P_BLOCK ($AUTOMATO_P_BLOCK_KIND)\" >> {}", log.display())),
+            rest_start: Some(String::from("echo 'no music player found' >&2; exit 3")),
+            session_abort: Some(format!("(sleep 1.5; echo left behind >> {}) & sleep 5", log.display())),
+            ..HookSet::default()
+        };
+
+        let (errors, reported) = mpsc::channel();
+        let mut events = EventHooks::new("Pomodoro", hooks, Duration::from_secs(1), errors);
+
+        let started = Instant::now();
+        events.block_started(&block(BlockKind::Work, 1));
+        events.block_started(&block(BlockKind::Rest, 1));
+        events.aborted();
+        assert!(started.elapsed() < Duration::from_millis(100), "hooks shouldn't hold up the timer");
+
+        drop(events);
+        //Long enough for anything the stopped hook left running to have written to the log
+        thread::sleep(Duration::from_secs(1));
+
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "work_start Pomodoro: Work block 1 (work)\n");
+
+        let reported: Vec<String> = reported.try_iter().map(|e| e.to_string()).collect();
+        assert_eq!(reported, [
+            "the rest_start hook failed with exit status: 3: no music player found",
+            "the session_abort hook was stopped after running for 1s",
+        ]);
+    }
+}
@@ -1,7 +1,27 @@
+pub mod calendar;
+pub mod checkpoint;
+pub mod clock;
+pub mod controls;
 pub mod format;
-
-use crate::app::{console, EXPECT_VERIFIED};
-use std::{time::Duration, thread, fmt::Display};
+pub mod hooks;
+pub mod notify;
+pub mod phase;
+pub mod plan;
+pub mod render;
+pub mod runner;
+pub mod sleep;
+pub mod tui;
+pub mod warning;
+
+use crate::{app::{app_settings::AppSettings, console, EXPECT_VERIFIED}, sound::{cues::SoundSet, SoundPlayer}};
+use clock::RealClock;
+use hooks::{EventHooks, HookError, HookSet};
+use controls::{ControlInput, Controls};
+use notify::DesktopNotifier;
+use render::{Renderer, SoundCues, TerminalRenderer};
+use phase::PhaseItem;
+use runner::{BlockKind, Blocks, Progress, RunSummary};
+use std::{fmt::Display, sync::mpsc, thread, time::Duration};
 use serde::{Serialize, Deserialize}; 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,7 +33,7 @@ pub enum RestType {
     Standard,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RepeatType {
     Infinite,
     Finite(u32),
@@ -27,10 +47,23 @@ pub struct Schedule {
     
     pub repeat_type: RepeatType,
     pub rest_type: RestType,
+
+    /// Set for schedules made of arbitrary phases, in which case they're run instead of the fields above.
+    /// Older schedules don't have this, and keep running as work/rest pairs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phases: Option<Vec<PhaseItem>>,
+
+    /// Sounds this schedule plays instead of the app's.
+    #[serde(default, skip_serializing_if = "SoundSet::is_empty")]
+    pub sounds: SoundSet,
+    /// Used instead of the app's warnings before a block ends, with an empty list turning them off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<Duration>>,
+    /// Commands this schedule runs instead of the app's.
+    #[serde(default, skip_serializing_if = "HookSet::is_empty")]
+    pub hooks: HookSet,
 }
 
-const QUARTER_SECOND: Duration = Duration::from_millis(250);
-const CONGRATS_TIME: Duration = Duration::from_millis(5000);
 impl Schedule {
     pub fn pomodoro() -> Schedule {
         Schedule { 
@@ -38,121 +71,147 @@ impl Schedule {
             work_duration: Duration::from_secs(60 * 25), 
             rest_duration: Duration::from_secs(60 * 5), 
             repeat_type: RepeatType::Finite(8), 
-            rest_type: RestType::LongRest { blocks_per_long_rest: 4, long_rest_duration: Duration::from_secs(60 * 30) }
+            rest_type: RestType::LongRest { blocks_per_long_rest: 4, long_rest_duration: Duration::from_secs(60 * 30) },
+            phases: None,
+            sounds: SoundSet::default(),
+            warnings: None,
+            hooks: HookSet::default(),
         }
     }
 
-    pub fn start(&self, alarm_path: Option<&str>) {
-        let mut dur = self.work_duration;
-        let mut working = true;
-        let mut block_count = 1;
+    /// A schedule that runs `phases`. The work/rest fields are filled in from them as best they can be,
+    /// so there's something sensible to fall back on if the phases are ever removed.
+    pub fn from_phases(name: String, phases: Vec<PhaseItem>) -> Schedule {
+        let first = |kind| phase::first(&phases, kind).map_or(Duration::ZERO, |p| p.duration);
+
+        Schedule {
+            name,
+            work_duration: first(BlockKind::Work),
+            rest_duration: first(BlockKind::Rest),
+            repeat_type: match phase::count(&phases, BlockKind::Work) {
+                Some(blocks) => RepeatType::Finite(blocks),
+                None => RepeatType::Infinite,
+            },
+            rest_type: RestType::Standard,
+            phases: Some(phases),
+            sounds: SoundSet::default(),
+            warnings: None,
+            hooks: HookSet::default(),
+        }
+    }
 
-        console::clear();
-        
-        println!("Working block 1");
-
-        loop {
-            console::move_cursor_to(0, 1);
-            console::clear_line();
-            print!("{}", format::dur_to_hhmmss(dur));
-            console::flush();
-
-            thread::sleep(QUARTER_SECOND);
-            
-            match dur.checked_sub(2 * QUARTER_SECOND) {
-                Some(new_dur) => dur = new_dur + QUARTER_SECOND,
-                None => {
-                    working = !working;
-
-                    if let Some(path) = alarm_path {
-                        let result = console::play_sound(path);
-                        //Wait a bit to see if the thing failed
-                        thread::sleep(Duration::from_millis(100));
-
-                        match result {
-                            Ok(mut proc) => {
-                                // Some(None) means the sound was really short and we're done
-                                // Some(status) if status.success() means the sound is currently playing, which means we're still good!
-                                if let Some(status) = proc.try_wait().expect("Expected wait for process to be valid") {
-                                    if !status.success() {
-                                        eprintln!("\nSound failed to play: \
-                                        check to make sure your sound path is correct");
-                                        thread::sleep(Duration::from_secs(2));
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                eprintln!("\nSound failed to play: \
-                                check to make sure mpg123 is installed (sudo apt install mpg123)");
-                                thread::sleep(Duration::from_secs(2));
-                            }
-                        }
-                    }
-                
-                    console::clear();
-
-                    if working {
-                        println!("Working block {}", block_count);
-                        dur = self.work_duration;
-                    } else {
-                        
-                        block_count += 1;
-
-                        if let RepeatType::Finite(repeats) = self.repeat_type {
-                            if block_count > repeats {
-                                println!("Congratulations, you've completed your schedule! 🎉🎉🎉");
-                                thread::sleep(CONGRATS_TIME);
-                                break;
-                            }
-                        }
-
-                        if let RestType::LongRest { blocks_per_long_rest, long_rest_duration } = self.rest_type {
-                            if block_count % blocks_per_long_rest == 1 && block_count != 1 {
-                                println!("Congratulations on completing {}{} blocks! Here's a deserved long break:",
-                                    if block_count == blocks_per_long_rest + 1 {
-                                        "your first "
-                                    } else {
-                                        "another "
-                                    },
-                                    blocks_per_long_rest.to_string(),
-                                );
-                                
-                                dur = long_rest_duration;
-                                continue;
-                            }
-                        }
-                        
-                        dur = self.rest_duration;
-
-                        println!("Rest block {}", block_count - 1);
-                    }
-                }
-            }
+    /// None if notifications are turned off.
+    fn notifier(&self, settings: &AppSettings) -> Result<Option<DesktopNotifier>, zbus::Error> {
+        if settings.notifications {
+            DesktopNotifier::connect(&self.name).map(Some)
+        } else {
+            Ok(None)
         }
     }
 
-    fn get_total_duration(&self) -> Option<Duration> {
-        match self.repeat_type {
-            RepeatType::Finite(blocks) => {
-                let total_work = self.work_duration * blocks;
-                let mut total_rest = self.rest_duration * (blocks - 1);
+    /// Runs the schedule in the terminal, from the beginning or from the `progress` of an earlier run.
+    /// `checkpointer` is told where the run is up to every so often, so it can be resumed if the app is closed.
+    pub fn start(&self, settings: &AppSettings, progress: Option<&Progress>, checkpointer: impl Renderer) -> RunSummary {
+        let notifier = self.notifier(settings).unwrap_or_else(|e| {
+            println!("Desktop notifications are turned on, but couldn't be sent ({e}). Carrying on without them.");
+            std::thread::sleep(Duration::from_secs(2));
+            None
+        });
 
-                if let RestType::LongRest { blocks_per_long_rest, long_rest_duration } = self.rest_type {
-                    let long_rests = (blocks - 1) / blocks_per_long_rest;
+        let (hook_errors, on_hook_error) = mpsc::channel();
+        let hooks = self.hooks(settings, hook_errors);
 
-                    total_rest += long_rest_duration * long_rests;
-                    total_rest -= self.rest_duration * long_rests;
-                }
+        let player = SoundPlayer::new(&settings.sound_players());
+        let terminal = TerminalRenderer::new(self, settings.sounds_for(&self.sounds), player, &on_hook_error);
+        let mut renderer = (((terminal, notifier), hooks), checkpointer);
+
+        let warnings = self.warnings.as_deref().unwrap_or(&settings.warnings);
+        let summary = runner::run(self, warnings, settings.on_sleep, progress, &RealClock, &mut ControlInput::new(), &mut renderer);
+
+        //The renderer puts the terminal back once it's dropped, after which the last hooks have had their chance to fail
+        drop(renderer);
+        let late_errors: Vec<HookError> = on_hook_error.try_iter().collect();
 
-                Some(total_work + total_rest)
+        if !late_errors.is_empty() {
+            for e in late_errors {
+                println!("Hook failed: {e}");
             }
-            RepeatType::Infinite => None,
+
+            println!("Press enter to continue");
+            console::get_input();
+        }
+
+        summary
+    }
+
+    /// Runs the schedule without a terminal, taking controls from `controls` and telling `renderer` how it's going.
+    /// Sounds and notifications work as usual, with anything that goes wrong with them printed to stderr.
+    pub fn run_detached(&self, settings: &AppSettings, controls: &mut impl Controls, renderer: impl Renderer) -> RunSummary {
+        let notifier = self.notifier(settings).unwrap_or_else(|e| {
+            eprintln!("Desktop notifications are turned on, but couldn't be sent ({e}). Carrying on without them.");
+            None
+        });
+
+        let (hook_errors, on_hook_error) = mpsc::channel();
+        let hooks = self.hooks(settings, hook_errors);
+
+        //Ends once the hooks have all been run and there's nothing left to report
+        let reporter = thread::spawn(move || {
+            for e in on_hook_error {
+                eprintln!("Hook failed: {e}");
+            }
+        });
+
+        let sounds = SoundCues::new(settings.sounds_for(&self.sounds), SoundPlayer::new(&settings.sound_players()));
+        let warnings = self.warnings.as_deref().unwrap_or(&settings.warnings);
+
+        let summary = runner::run(self, warnings, settings.on_sleep, None, &RealClock, controls, &mut (((sounds, notifier), hooks), renderer));
+        reporter.join().ok();
+
+        summary
+    }
+
+    /// Runs the schedule's hooks, and the app's for anything it doesn't have its own hook for.
+    fn hooks(&self, settings: &AppSettings, errors: mpsc::Sender<HookError>) -> EventHooks {
+        EventHooks::new(&self.name, self.hooks.or(&settings.hooks), settings.hook_timeout(), errors)
+    }
+
+    /// None for schedules that go on until they're quit.
+    pub fn total_blocks(&self) -> Option<usize> {
+        //Counted rather than run through, as nested groups can add up to more blocks than could ever be gone through
+        let phases = self.phases();
+
+        [BlockKind::Work, BlockKind::Rest, BlockKind::LongRest].into_iter()
+            .try_fold(0u32, |total, kind| Some(total.saturating_add(phase::count(&phases, kind)?)))
+            .map(|total| total as usize)
+    }
+
+    pub fn blocks(&self) -> Blocks {
+        Blocks::new(self)
+    }
+
+    /// The phases this schedule runs through, converting work/rest schedules into them.
+    pub fn phases(&self) -> Vec<PhaseItem> {
+        match &self.phases {
+            Some(phases) => phases.clone(),
+            None => phase::from_classic(self),
         }
     }
+
+    fn get_total_duration(&self) -> Option<Duration> {
+        phase::total_duration(&self.phases())
+    }
 }
 
 impl Display for Schedule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(phases) = &self.phases {
+            return match self.get_total_duration() {
+                Some(total) => write!(f, "{}: {} ({})", self.name, phase::to_spec(phases), format::dur_to_xhxmxs(total)),
+                None => write!(f, "{}: {}", self.name, phase::to_spec(phases)),
+            };
+        }
+
         write!(f, "{name}: {work_dur} work, {rest_dur} rest{rest_type_details}{repeat_type_details}",
         
             name = self.name,
@@ -175,9 +234,91 @@ impl Display for Schedule {
 #[cfg(test)]
 #[allow(unused_imports, dead_code)]
 mod tests {
-    use std::time::SystemTime;
+    use std::{time::{Instant, SystemTime}, thread};
 
     use super::*;
+    use clock::{Clock, ManualClock};
+    use controls::{Control, Controls};
+    use render::Renderer;
+    use runner::{Block, BlockKind, Outcome, Progress, CHECKPOINT_INTERVAL, CONGRATS_TIME};
+    use sleep::SleepPolicy;
+
+    /// Never sends a control, only lets the clock pass the time.
+    struct Idle<'a>(&'a ManualClock);
+
+    impl Controls for Idle<'_> {
+        fn poll(&mut self, timeout: Duration) -> Option<Control> {
+            self.0.sleep(timeout);
+            None
+        }
+    }
+
+    /// Never sends a control, waiting out each poll in real time like nobody pressing anything would.
+    struct NoInput;
+
+    impl Controls for NoInput {
+        fn poll(&mut self, timeout: Duration) -> Option<Control> {
+            thread::sleep(timeout);
+            None
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        started: Vec<Block>,
+        warned: Vec<Duration>,
+        checkpoints: Vec<Progress>,
+        woke: Vec<(Duration, SleepPolicy)>,
+        completed: bool,
+    }
+
+    impl Renderer for Recorder {
+        fn block_started(&mut self, block: &Block) {
+            self.started.push(block.clone());
+        }
+
+        fn block_ending(&mut self, _block: &Block, before_end: Duration) {
+            self.warned.push(before_end);
+        }
+
+        fn completed(&mut self) {
+            self.completed = true;
+        }
+
+        fn checkpoint(&mut self, _block: &Block, progress: &Progress) {
+            self.checkpoints.push(progress.clone());
+        }
+
+        fn woke(&mut self, asleep: Duration, policy: SleepPolicy) {
+            self.woke.push((asleep, policy));
+        }
+    }
+
+    /// Puts the computer to sleep for `asleep` once `at` has passed, then sends `then` when it wakes up.
+    struct SleepAt<'a> {
+        clock: &'a ManualClock,
+        at: Duration,
+        asleep: Duration,
+        then: Option<Control>,
+        slept: bool,
+    }
+
+    impl Controls for SleepAt<'_> {
+        fn poll(&mut self, timeout: Duration) -> Option<Control> {
+            self.clock.sleep(timeout);
+
+            if self.slept {
+                return self.then.take();
+            }
+
+            if self.clock.elapsed() >= self.at {
+                self.clock.suspend(self.asleep);
+                self.slept = true;
+            }
+
+            None
+        }
+    }
 
     fn dur_close_enough(dur1: Duration, dur2: Duration, threshold_ms: u128) -> bool {
         let ms1 = dur1.as_millis();
@@ -193,6 +334,10 @@ mod tests {
             rest_duration: Duration::from_secs(5*60), 
             rest_type: RestType::LongRest { blocks_per_long_rest: 4, long_rest_duration: Duration::from_secs(30*60) }, 
             repeat_type: RepeatType::Finite(8),
+            phases: None,
+            sounds: SoundSet::default(),
+            warnings: None,
+            hooks: HookSet::default(),
         }
     }
 
@@ -203,6 +348,10 @@ mod tests {
             rest_duration: Duration::from_secs(1), 
             repeat_type: RepeatType::Infinite,
             rest_type: RestType::Standard,
+            phases: None,
+            sounds: SoundSet::default(),
+            warnings: None,
+            hooks: HookSet::default(),
         }
     }
     
@@ -214,6 +363,7 @@ mod tests {
     }
 
     #[test]
+    #[ignore = "runs on the wall clock, so it's slow and fails on busy machines"]
     fn schedule_should_last_close_to_its_duration() {
         let schedule = test_bounded();
         let schedule_duration = schedule.get_total_duration().unwrap() + CONGRATS_TIME;
@@ -222,10 +372,10 @@ mod tests {
         assert!(schedule_duration.as_millis() < 10000, "actual duration was {}", schedule_duration.as_millis());
 
         let before = SystemTime::now();
-        
-        thread::spawn(move || {
-            schedule.start(None);
-        }).join().unwrap();
+        let mut recorder = Recorder::default();
+
+        runner::run(&schedule, &[], SleepPolicy::default(), None, &RealClock, &mut NoInput, &mut recorder);
+        assert!(recorder.completed);
 
         let passed_time = {
             let now = SystemTime::now();
@@ -235,7 +385,158 @@ mod tests {
         assert!(dur_close_enough(
             passed_time,
             schedule_duration,
-            1000
+            50
         ), "passed_time: {passed_time:?}, duration: {schedule_duration:?}");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn simulated_pomodoro_should_take_exactly_its_duration() {
+        let schedule = pomodoro();
+        let clock = ManualClock::new();
+        let mut recorder = Recorder::default();
+
+        let before = Instant::now();
+        runner::run(&schedule, &[Duration::from_secs(60)], SleepPolicy::Count, None, &clock, &mut Idle(&clock), &mut recorder);
+
+        assert!(before.elapsed() < Duration::from_secs(1), "simulated run took {:?}", before.elapsed());
+        assert!(recorder.completed);
+        assert_eq!(recorder.started.iter().filter(|b| b.kind == BlockKind::Work).count(), 8);
+        assert_eq!(recorder.warned.len(), recorder.started.len(), "every block should be warned about once");
+        assert_eq!(clock.elapsed(), schedule.get_total_duration().unwrap() + CONGRATS_TIME);
+
+        let total = schedule.get_total_duration().unwrap();
+        assert!(recorder.checkpoints.len() as u32 >= total.as_secs() as u32 / CHECKPOINT_INTERVAL.as_secs() as u32);
+        assert!(recorder.checkpoints.windows(2).all(|w| w[1].elapsed - w[0].elapsed <= CHECKPOINT_INTERVAL), "checkpoints should never be far apart");
+        assert_eq!((0..15).collect::<Vec<_>>(), {
+            let mut positions: Vec<_> = recorder.checkpoints.iter().map(|p| p.position).collect();
+            positions.dedup();
+            positions
+        }, "every block should be checkpointed");
+    }
+
+    /// Quits after `polls` polls, passing the time as it goes.
+    struct AbortAfter<'a> {
+        clock: &'a ManualClock,
+        polls: u32,
+    }
+
+    impl Controls for AbortAfter<'_> {
+        fn poll(&mut self, timeout: Duration) -> Option<Control> {
+            self.clock.sleep(timeout);
+            self.polls = self.polls.saturating_sub(1);
+
+            (self.polls == 0).then_some(Control::Abort)
+        }
+    }
+
+    #[test]
+    fn blocks_that_end_as_soon_as_they_start_should_still_wait_for_input() {
+        let mut schedule = test();
+        schedule.work_duration = Duration::ZERO;
+        schedule.rest_duration = Duration::ZERO;
+
+        let clock = ManualClock::new();
+        let mut recorder = Recorder::default();
+        let summary = runner::run(&schedule, &[], SleepPolicy::Count, None, &clock, &mut AbortAfter { clock: &clock, polls: 20 }, &mut recorder);
+
+        assert_eq!(summary.outcome, Outcome::Aborted);
+        assert!(recorder.started.len() <= 21, "only one block should start per poll, but {} did", recorder.started.len());
+        assert!(clock.elapsed() >= 19 * runner::TRANSITION_WAIT, "only {:?} passed", clock.elapsed());
+    }
+
+    /// Waits a little longer than asked every time, like a real computer that's busy with other things.
+    struct Oversleep<'a> {
+        clock: &'a ManualClock,
+        by: Duration,
+    }
+
+    impl Controls for Oversleep<'_> {
+        fn poll(&mut self, timeout: Duration) -> Option<Control> {
+            self.clock.sleep(timeout + self.by);
+            None
+        }
+    }
+
+    /// When each block started, going by the clock.
+    struct StartTimes<'a> {
+        clock: &'a ManualClock,
+        started: Vec<(Duration, Duration)>,
+    }
+
+    impl Renderer for StartTimes<'_> {
+        fn block_started(&mut self, block: &Block) {
+            self.started.push((self.clock.elapsed(), block.duration));
+        }
+    }
+
+    #[test]
+    fn late_wakeups_should_never_add_up_over_many_blocks() {
+        let schedule = Schedule::from_phases(String::from("many"), phase::parse("300x(w 0:07, r 0:03 rest)").unwrap());
+        let clock = ManualClock::new();
+        let late = Duration::from_millis(7);
+        let mut starts = StartTimes { clock: &clock, started: Vec::new() };
+
+        runner::run(&schedule, &[], SleepPolicy::Count, None, &clock, &mut Oversleep { clock: &clock, by: late }, &mut starts);
+
+        assert_eq!(starts.started.len(), 600);
+        let mut due = Duration::ZERO;
+
+        for (i, &(started, duration)) in starts.started.iter().enumerate() {
+            assert!(started >= due && started - due <= late, "block {i} started at {started:?} but was due at {due:?}");
+            due += duration;
+        }
+
+        let total = schedule.get_total_duration().unwrap() + CONGRATS_TIME;
+        assert!(clock.elapsed() - total <= late, "the whole run took {:?} longer than it should have", clock.elapsed() - total);
+    }
+
+    #[test]
+    fn resumed_pomodoro_should_only_run_what_was_left() {
+        let schedule = pomodoro();
+        let clock = ManualClock::new();
+        let mut recorder = Recorder::default();
+
+        //Part way through the last work block
+        let progress = Progress { position: 14, remaining: Duration::from_secs(600), work_blocks_completed: 7, ..Progress::default() };
+        let summary = runner::run(&schedule, &[], SleepPolicy::Count, Some(&progress), &clock, &mut Idle(&clock), &mut recorder);
+
+        assert_eq!(recorder.started.len(), 1);
+        assert_eq!(recorder.started[0].index, 8);
+        assert_eq!(clock.elapsed(), Duration::from_secs(600) + CONGRATS_TIME);
+        assert_eq!(summary.work_blocks_completed, 8);
+        assert_eq!(recorder.checkpoints.first(), Some(&progress), "the first checkpoint should be right where it left off");
+    }
+
+    #[test]
+    fn time_asleep_should_count_or_not_depending_on_the_policy() {
+        let schedule = pomodoro();
+        let total = schedule.get_total_duration().unwrap() + CONGRATS_TIME;
+        let min = Duration::from_secs(60);
+
+        for (policy, then, counted) in [
+            (SleepPolicy::Count, None, true),
+            (SleepPolicy::Pause, Some(Control::TogglePause), false),
+            (SleepPolicy::Ask, Some(Control::CountSleep), true),
+            (SleepPolicy::Ask, Some(Control::TogglePause), false),
+        ] {
+            let clock = ManualClock::new();
+            let mut recorder = Recorder::default();
+            //Asleep from 10 minutes into the first work block until 20 minutes into the second
+            let mut controls = SleepAt { clock: &clock, at: 10 * min, asleep: 40 * min, then, slept: false };
+
+            let summary = runner::run(&schedule, &[], policy, None, &clock, &mut controls, &mut recorder);
+
+            assert_eq!(recorder.woke.len(), 1, "{policy:?}");
+            assert!(recorder.woke[0].0 >= 40 * min && recorder.woke[0].1 == policy);
+            assert_eq!(summary.work_blocks_completed, 8);
+
+            if counted {
+                assert!(dur_close_enough(clock.elapsed(), total - 40 * min, 1000), "{policy:?}: {:?}", clock.elapsed());
+                assert_eq!(recorder.started.len(), 14, "{policy:?}: the rest slept through is never started");
+            } else {
+                assert!(dur_close_enough(clock.elapsed(), total, 1000), "{policy:?}: {:?}", clock.elapsed());
+                assert_eq!(recorder.started.len(), 15, "{policy:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::HashMap, time::Duration};
+
+use zbus::{blocking::{connection, Connection}, zvariant::Value};
+
+use super::{format, render::Renderer, runner::{Block, BlockKind}};
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+/// Long enough for a notification daemon that's being started up on demand, short enough not to hold up the timer much.
+const METHOD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Sends a desktop notification whenever a block starts, through the freedesktop notification service on the session bus.
+pub struct DesktopNotifier {
+    connection: Connection,
+    schedule_name: String,
+    /// Each notification replaces the last, so they don't pile up while nobody's looking.
+    last_id: u32,
+    started_first_block: bool,
+    /// Set after a notification fails to send, as the daemon has most likely gone away.
+    failed: bool,
+}
+
+impl DesktopNotifier {
+    /// Fails if there's no session bus, or nothing on it to show notifications.
+    pub fn connect(schedule_name: &str) -> Result<DesktopNotifier, zbus::Error> {
+        DesktopNotifier::build(connection::Builder::session()?, schedule_name)
+    }
+
+    pub fn connect_to(address: &str, schedule_name: &str) -> Result<DesktopNotifier, zbus::Error> {
+        DesktopNotifier::build(connection::Builder::address(address)?, schedule_name)
+    }
+
+    fn build(builder: connection::Builder, schedule_name: &str) -> Result<DesktopNotifier, zbus::Error> {
+        let connection = builder.method_timeout(METHOD_TIMEOUT).build()?;
+
+        //Connecting to the bus works fine without a notification daemon, so check one is actually there
+        connection.call_method(Some(DESTINATION), PATH, Some(INTERFACE), "GetServerInformation", &())?;
+
+        Ok(DesktopNotifier {
+            connection,
+            schedule_name: schedule_name.to_string(),
+            last_id: 0,
+            started_first_block: false,
+            failed: false,
+        })
+    }
+
+    fn notify(&mut self, summary: &str, body: &str) {
+        if self.failed {
+            return;
+        }
+
+        let actions: Vec<&str> = Vec::new();
+        let hints: HashMap<&str, Value> = HashMap::new();
+        let args = ("automato-p", self.last_id, "", summary, body, actions, hints, -1i32);
+
+        let sent = self.connection.call_method(Some(DESTINATION), PATH, Some(INTERFACE), "Notify", &args)
+            .and_then(|reply| reply.body().deserialize::<u32>());
+
+        match sent {
+            Ok(id) => self.last_id = id,
+            Err(_) => self.failed = true,
+        }
+    }
+}
+
+fn describe(block: &Block) -> String {
+    match block.kind {
+        BlockKind::Work => block.name(),
+        _ if block.index == 0 => block.label.clone(),
+        _ => format!("{} after work block {}", block.label, block.index),
+    }
+}
+
+impl Renderer for DesktopNotifier {
+    fn block_started(&mut self, block: &Block) {
+        //They've only just started the schedule themselves, so they don't need telling
+        if !self.started_first_block {
+            self.started_first_block = true;
+            return;
+        }
+
+        let summary = match block.kind {
+            BlockKind::Work => "Time to work",
+            BlockKind::Rest => "Time for a rest",
+            BlockKind::LongRest => "Time for a long rest",
+        };
+
+        self.notify(summary, &format!("{}, {} ({})", describe(block), format::dur_to_xhxmxs(block.duration), self.schedule_name));
+    }
+
+    fn block_ending(&mut self, block: &Block, before_end: Duration) {
+        self.notify(&format!("{} ends in {}", block.name(), format::dur_to_xhxmxs(before_end)), &self.schedule_name.clone());
+    }
+
+    fn completed(&mut self) {
+        self.notify("Schedule complete", &format!("You've finished {}!", self.schedule_name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{BufRead, BufReader}, process::{Command, Stdio}, sync::{Arc, Mutex}};
+
+    use super::*;
+
+    /// Stands in for a notification daemon, remembering the summary and body of everything it's sent.
+    struct MockDaemon(Arc<Mutex<Vec<(String, String)>>>);
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl MockDaemon {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(&self, _app_name: String, replaces_id: u32, _app_icon: String, summary: String, body: String,
+            _actions: Vec<String>, _hints: HashMap<String, zbus::zvariant::OwnedValue>, _expire_timeout: i32) -> u32 {
+            let mut sent = self.0.lock().unwrap();
+            sent.push((summary, body));
+
+            if replaces_id == 0 {sent.len() as u32} else {replaces_id}
+        }
+
+        fn get_server_information(&self) -> (String, String, String, String) {
+            (String::from("mock"), String::from("automato-p"), String::from("1"), String::from("1.2"))
+        }
+    }
+
+    /// A private session bus that's shut down when dropped.
+    struct Bus(std::process::Child, String);
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            self.0.kill().ok();
+            self.0.wait().ok();
+        }
+    }
+
+    fn start_bus() -> Option<Bus> {
+        let mut child = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        BufReader::new(child.stdout.as_mut()?).read_line(&mut address).ok()?;
+
+        Some(Bus(child, address.trim().to_string()))
+    }
+
+    fn block(kind: BlockKind, index: u32, label: &str) -> Block {
+        Block { kind, index, duration: Duration::from_secs(5 * 60), label: label.to_string() }
+    }
+
+    #[test]
+    fn transitions_should_be_sent_to_the_notification_daemon() {
+        let Some(bus) = start_bus() else {
+            eprintln!("Skipping, as dbus-daemon couldn't be started");
+            return;
+        };
+
+        assert!(DesktopNotifier::connect_to(&bus.1, "Pomodoro").is_err(), "there's no daemon on the bus yet");
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let _daemon = connection::Builder::address(bus.1.as_str()).unwrap()
+            .name(DESTINATION).unwrap()
+            .serve_at(PATH, MockDaemon(Arc::clone(&sent))).unwrap()
+            .build().unwrap();
+
+        let mut notifier = DesktopNotifier::connect_to(&bus.1, "Pomodoro").unwrap();
+        notifier.block_started(&block(BlockKind::Work, 1, "Work"));
+        notifier.block_started(&block(BlockKind::Rest, 1, "Rest"));
+        notifier.block_started(&block(BlockKind::Work, 2, "Work"));
+        notifier.block_ending(&block(BlockKind::Work, 2, "Work"), Duration::from_secs(60));
+        notifier.completed();
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            (String::from("Time for a rest"), String::from("Rest after work block 1, 5m (Pomodoro)")),
+            (String::from("Time to work"), String::from("Work block 2, 5m (Pomodoro)")),
+            (String::from("Work block 2 ends in 1m"), String::from("Pomodoro")),
+            (String::from("Schedule complete"), String::from("You've finished Pomodoro!")),
+        ]);
+    }
+}
@@ -0,0 +1,353 @@
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+use super::{format, runner::BlockKind, RepeatType, RestType, Schedule};
+
+/// One stretch of a schedule, like "warm-up, 10 minutes, rest".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phase {
+    pub label: String,
+    pub duration: Duration,
+    pub kind: BlockKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PhaseItem {
+    Phase(Phase),
+    /// Runs `items` in order, `times` times over. Groups can be nested.
+    Repeat {
+        times: RepeatType,
+        items: Vec<PhaseItem>,
+    },
+}
+
+impl Phase {
+    pub fn new(label: &str, duration: Duration, kind: BlockKind) -> Phase {
+        Phase { label: label.to_string(), duration, kind }
+    }
+}
+
+/// The phases a schedule with only work and rest durations runs through.
+pub fn from_classic(schedule: &Schedule) -> Vec<PhaseItem> {
+    let work = || PhaseItem::Phase(Phase::new("Work", schedule.work_duration, BlockKind::Work));
+    let rest = || PhaseItem::Phase(Phase::new("Rest", schedule.rest_duration, BlockKind::Rest));
+
+    let long_rest = match schedule.rest_type {
+        RestType::LongRest { blocks_per_long_rest, long_rest_duration } if blocks_per_long_rest != 0 => {
+            Some((blocks_per_long_rest, PhaseItem::Phase(Phase::new("Long rest", long_rest_duration, BlockKind::LongRest))))
+        }
+        _ => None,
+    };
+
+    match schedule.repeat_type {
+        //Spelled out in full, as there's no rest after the last work block
+        RepeatType::Finite(blocks) => {
+            let mut items = Vec::new();
+
+            for i in 1..=blocks {
+                items.push(work());
+
+                if i == blocks {
+                    break;
+                }
+
+                match &long_rest {
+                    Some((every, long)) if i % every == 0 => items.push(long.clone()),
+                    _ => items.push(rest()),
+                }
+            }
+
+            items
+        }
+        RepeatType::Infinite => {
+            let cycle = match long_rest {
+                Some((every, long)) => {
+                    let mut cycle = vec![PhaseItem::Repeat { times: RepeatType::Finite(every - 1), items: vec![work(), rest()] }];
+                    cycle.push(work());
+                    cycle.push(long);
+                    cycle
+                }
+                None => vec![work(), rest()],
+            };
+
+            vec![PhaseItem::Repeat { times: RepeatType::Infinite, items: cycle }]
+        }
+    }
+}
+
+/// Whether running these items would ever produce a phase, so empty groups can't spin forever.
+fn has_phases(items: &[PhaseItem]) -> bool {
+    items.iter().any(|item| match item {
+        PhaseItem::Phase(_) => true,
+        PhaseItem::Repeat { times: RepeatType::Finite(0), .. } => false,
+        PhaseItem::Repeat { items, .. } => has_phases(items),
+    })
+}
+
+/// None if the items repeat forever.
+pub fn total_duration(items: &[PhaseItem]) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+
+    for item in items {
+        total = total.saturating_add(match item {
+            PhaseItem::Phase(phase) => phase.duration,
+            PhaseItem::Repeat { .. } if !has_phases(std::slice::from_ref(item)) => Duration::ZERO,
+            PhaseItem::Repeat { times: RepeatType::Finite(n), items } => total_duration(items)?.saturating_mul(*n),
+            PhaseItem::Repeat { times: RepeatType::Infinite, .. } => return None,
+        });
+    }
+
+    Some(total)
+}
+
+/// How many of the phases the items run through are `kind` blocks, or None if the items repeat forever.
+pub fn count(items: &[PhaseItem], kind: BlockKind) -> Option<u32> {
+    let mut total: u32 = 0;
+
+    for item in items {
+        total = total.saturating_add(match item {
+            PhaseItem::Phase(phase) => u32::from(phase.kind == kind),
+            PhaseItem::Repeat { .. } if !has_phases(std::slice::from_ref(item)) => 0,
+            PhaseItem::Repeat { times: RepeatType::Finite(n), items } => count(items, kind)?.saturating_mul(*n),
+            PhaseItem::Repeat { times: RepeatType::Infinite, .. } => return None,
+        });
+    }
+
+    Some(total)
+}
+
+/// The first `kind` phase the items run through, without having to run through the ones before it.
+pub fn first(items: &[PhaseItem], kind: BlockKind) -> Option<&Phase> {
+    items.iter().find_map(|item| match item {
+        PhaseItem::Phase(phase) => Some(phase).filter(|phase| phase.kind == kind),
+        PhaseItem::Repeat { times: RepeatType::Finite(0), .. } => None,
+        PhaseItem::Repeat { items, .. } => first(items, kind),
+    })
+}
+
+/// Flattens the items into the phases they run through, in order.
+pub struct Phases {
+    //Each group being run, innermost last, with where it's up to and how many more times it has to run (None for forever)
+    stack: Vec<(Vec<PhaseItem>, usize, Option<u32>)>,
+}
+
+impl Phases {
+    pub fn new(items: Vec<PhaseItem>) -> Phases {
+        Phases { stack: vec![(items, 0, Some(1))] }
+    }
+}
+
+impl Iterator for Phases {
+    type Item = Phase;
+
+    fn next(&mut self) -> Option<Phase> {
+        loop {
+            let (items, pos, times_left) = self.stack.last_mut()?;
+
+            if *pos == items.len() {
+                match times_left {
+                    Some(n) if *n > 1 => {
+                        *n -= 1;
+                        *pos = 0;
+                    }
+                    None => *pos = 0,
+                    Some(_) => {
+                        self.stack.pop();
+                    }
+                }
+
+                continue;
+            }
+
+            let item = items[*pos].clone();
+            *pos += 1;
+
+            match item {
+                PhaseItem::Phase(phase) => return Some(phase),
+                PhaseItem::Repeat { times, items } => {
+                    if !has_phases(&items) {
+                        continue;
+                    }
+
+                    let times_left = match times {
+                        RepeatType::Finite(n) => Some(n),
+                        RepeatType::Infinite => None,
+                    };
+
+                    self.stack.push((items, 0, times_left));
+                }
+            }
+        }
+    }
+}
+
+fn kind_name(kind: BlockKind) -> Option<&'static str> {
+    match kind {
+        BlockKind::Work => None,
+        BlockKind::Rest => Some("rest"),
+        BlockKind::LongRest => Some("long-rest"),
+    }
+}
+
+/// Writes items out the way `parse` reads them, e.g. `warm-up 10:00 rest, 3x(work 50:00, break 10:00 rest), review 15:00`.
+pub fn to_spec(items: &[PhaseItem]) -> String {
+    items.iter()
+        .map(|item| match item {
+            PhaseItem::Phase(phase) => {
+                let mut spec = format!("{} {}", phase.label, format::dur_to_hhmmss(phase.duration));
+
+                if let Some(kind) = kind_name(phase.kind) {
+                    spec.push(' ');
+                    spec.push_str(kind);
+                }
+
+                spec
+            }
+            PhaseItem::Repeat { times: RepeatType::Finite(n), items } => format!("{n}x({})", to_spec(items)),
+            PhaseItem::Repeat { times: RepeatType::Infinite, items } => format!("*({})", to_spec(items)),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Reads a comma separated list of phases and groups. A phase is `<label> <HH:MM:SS> [work|rest|long-rest]` (work if left out),
+/// and a group is `<N>x(...)`, or `*(...)` to repeat forever.
+pub fn parse(spec: &str) -> Result<Vec<PhaseItem>, String> {
+    let (items, rest) = parse_items(spec)?;
+
+    if !rest.trim().is_empty() {
+        return Err(format!("unexpected '{}'", rest.trim()));
+    }
+
+    if !has_phases(&items) {
+        return Err(String::from("there has to be at least one phase"));
+    }
+
+    Ok(items)
+}
+
+/// Parses items until the end of the string or an unmatched ')', returning what's left over.
+fn parse_items(mut spec: &str) -> Result<(Vec<PhaseItem>, &str), String> {
+    let mut items = Vec::new();
+
+    loop {
+        spec = spec.trim_start();
+
+        if spec.is_empty() || spec.starts_with(')') {
+            return Ok((items, spec));
+        }
+
+        let group_start = spec.find('(');
+        let item_end = spec.find([',', ')']).unwrap_or(spec.len());
+
+        if let Some(open) = group_start.filter(|&open| open < item_end) {
+            let times = match spec[..open].trim() {
+                "*" => RepeatType::Infinite,
+                count => {
+                    let count = count.strip_suffix(['x', 'X']).unwrap_or(count).trim();
+
+                    RepeatType::Finite(count.parse().map_err(|_| format!("'{count}' should be a number of times to repeat, or * for forever"))?)
+                }
+            };
+
+            let (group, rest) = parse_items(&spec[open + 1..])?;
+            let rest = rest.strip_prefix(')').ok_or_else(|| String::from("a group is missing its closing ')'"))?;
+
+            items.push(PhaseItem::Repeat { times, items: group });
+            spec = rest;
+        } else {
+            items.push(PhaseItem::Phase(parse_phase(&spec[..item_end])?));
+            spec = &spec[item_end..];
+        }
+
+        spec = spec.trim_start();
+
+        if let Some(rest) = spec.strip_prefix(',') {
+            spec = rest;
+        }
+    }
+}
+
+fn parse_phase(spec: &str) -> Result<Phase, String> {
+    let words: Vec<&str> = spec.split_whitespace().collect();
+
+    let (kind, words) = match words.split_last() {
+        Some((&"work", rest)) => (BlockKind::Work, rest),
+        Some((&"rest", rest)) => (BlockKind::Rest, rest),
+        Some((&"long-rest", rest)) => (BlockKind::LongRest, rest),
+        _ => (BlockKind::Work, &words[..]),
+    };
+
+    let [label, duration] = words else {
+        return Err(format!("'{}' should look like <label> <HH:MM:SS> [work|rest|long-rest]", spec.trim()));
+    };
+
+    let duration = match format::try_hhmmss_to_dur(duration) {
+        Some(dur) if dur.is_zero() => return Err(format!("'{duration}' has to be longer than zero - {label}")),
+        Some(dur) => dur,
+        None => return Err(format!("'{duration}' could not be converted into an HH:MM:SS duration - {label}")),
+    };
+
+    Ok(Phase::new(label, duration, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specs_should_round_trip() {
+        let spec = "warm-up 10:00 rest, 3x(work 50:00, break 10:00 rest), lunch 1:00:00 long-rest, *(work 52:00, break 17:00 rest)";
+        let items = parse(spec).unwrap();
+
+        assert_eq!(to_spec(&items), spec);
+        assert_eq!(total_duration(&items), None);
+        assert_eq!(total_duration(&items[..3]), Some(Duration::from_secs((10 + 3 * 60 + 60) * 60)));
+    }
+
+    #[test]
+    fn nested_groups_should_flatten_in_order() {
+        let items = parse("2x(a 1, 2x(b 2, c 3 rest)), d 4").unwrap();
+        let labels: String = Phases::new(items).map(|p| p.label).collect();
+
+        assert_eq!(labels, "abcbcabcbcd");
+    }
+
+    #[test]
+    fn bad_specs_should_be_rejected() {
+        assert!(parse("").is_err());
+        assert!(parse("3x(work 50:00").is_err());
+        assert!(parse("work fifty").is_err());
+        assert!(parse("*(0x(work 1:00))").is_err(), "groups that can't produce anything would spin forever");
+    }
+
+    #[test]
+    fn blocks_should_be_counted_from_the_whole_list() {
+        let items = parse("warm-up 1 rest, 600x(w 1, r 1 rest)").unwrap();
+        assert_eq!(count(&items, BlockKind::Work), Some(600));
+        assert_eq!(count(&items, BlockKind::Rest), Some(601));
+        assert_eq!(count(&parse("a 1, *(b 1)").unwrap(), BlockKind::Work), None);
+    }
+
+    #[test]
+    fn huge_repeats_should_be_measured_without_running_through_them() {
+        let items = parse("4294967295x(4294967295x(a 1:00)), 2x(b 5:00 rest)").unwrap();
+        assert_eq!(total_duration(&items), Some(Duration::MAX));
+        assert_eq!(count(&items, BlockKind::Work), Some(u32::MAX));
+        assert_eq!(first(&items, BlockKind::Rest).map(|p| p.label.as_str()), Some("b"));
+        assert_eq!(first(&parse("0x(a 1), b 2").unwrap(), BlockKind::Work).map(|p| p.label.as_str()), Some("b"));
+
+        let schedule = Schedule::from_phases(String::from("Huge"), items);
+        assert_eq!((schedule.work_duration, schedule.rest_duration), (Duration::from_secs(60), Duration::from_secs(300)));
+        assert!(schedule.to_string().starts_with("Huge: "));
+        assert_eq!(schedule.total_blocks(), Some(u32::MAX as usize));
+    }
+
+    #[test]
+    fn zero_length_phases_should_be_rejected() {
+        //They'd be over as soon as they started, which for an infinite group means forever
+        assert_eq!(parse("*(a 0)").unwrap_err(), "'0' has to be longer than zero - a");
+        assert!(parse("work 50:00, 2x(break 00:00 rest)").is_err());
+        assert!(parse("a 0:01").is_ok());
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{DateTime, Local, TimeZone};
+
+use super::{runner::{Block, BlockKind}, Schedule};
+
+/// Stops plans of infinite schedules that never reach the work block they're meant to end on, like ones made only of rests.
+const MAX_PLANNED_BLOCKS: usize = 10_000;
+
+/// A block of a schedule along with when it would run.
+#[derive(Debug, Clone)]
+pub struct PlannedBlock<Tz: TimeZone = Local> {
+    pub block: Block,
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+}
+
+/// When every block would run if the schedule were started at `start` and nothing was paused, skipped or extended.
+/// `work_blocks` ends the plan after that many work blocks, the same way a finite schedule ends. Infinite schedules need it,
+/// so None is returned for them without it. The plan stops early at a block that would end too far in the future to represent.
+pub fn plan<Tz: TimeZone>(schedule: &Schedule, start: DateTime<Tz>, work_blocks: Option<u32>) -> Option<Vec<PlannedBlock<Tz>>> {
+    if work_blocks.is_none() && schedule.get_total_duration().is_none() {
+        return None;
+    }
+
+    let mut planned = Vec::new();
+    let mut next_start = start;
+
+    for block in schedule.blocks().take(MAX_PLANNED_BLOCKS) {
+        //Anything after the last work block, including the rest that would follow it
+        if let Some(limit) = work_blocks {
+            if block.index > limit || (block.index == limit && block.kind != BlockKind::Work) {
+                break;
+            }
+        }
+
+        //Blocks so long they'd end past the end of the calendar can't be planned, and neither can anything after them
+        let Some(end) = chrono::Duration::from_std(block.duration).ok().and_then(|dur| next_start.clone().checked_add_signed(dur)) else {
+            break;
+        };
+
+        planned.push(PlannedBlock { block, start: next_start, end: end.clone() });
+        next_start = end;
+    }
+
+    Some(planned)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::schedule::{phase, RepeatType, RestType};
+
+    #[test]
+    fn infinite_schedules_should_need_a_block_count() {
+        let mut schedule = Schedule::pomodoro();
+        schedule.repeat_type = RepeatType::Infinite;
+        schedule.rest_type = RestType::Standard;
+        let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+
+        assert!(plan(&schedule, start, None).is_none());
+
+        let planned = plan(&schedule, start, Some(3)).unwrap();
+        assert_eq!(planned.len(), 5, "3 work blocks and the 2 rests between them");
+        assert_eq!(planned.last().unwrap().end, Utc.with_ymd_and_hms(2026, 10, 19, 10, 25, 0).unwrap());
+
+        let rests_first = Schedule::from_phases(String::from("test"), phase::parse("settle 5:00 rest, *(work 25:00, rest 5:00 rest)").unwrap());
+        let labels: Vec<String> = plan(&rests_first, start, Some(1)).unwrap().into_iter().map(|p| p.block.label).collect();
+        assert_eq!(labels, vec!["settle", "work"]);
+    }
+
+    #[test]
+    fn blocks_too_long_for_the_calendar_should_end_the_plan() {
+        let mut schedule = Schedule::pomodoro();
+        //About 300,000 years, which is further than chrono's calendar goes
+        schedule.rest_duration = std::time::Duration::from_secs(300_000 * 365 * 24 * 60 * 60);
+        let start = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+
+        let planned = plan(&schedule, start, None).unwrap();
+        assert_eq!(planned.len(), 1, "only the first work block can be planned");
+        assert_eq!(planned[0].block.kind, BlockKind::Work);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{sync::mpsc::Receiver, time::Duration};
+
+use crate::{app::console, sound::{cues::{Cue, CueSound, SoundSet}, SoundPlayer}};
+use super::{format, hooks::HookError, runner::{Block, BlockKind, Progress, Tick}, sleep::SleepPolicy, tui::{Screen, View}, RestType, Schedule};
+
+/// Gets told about everything that happens while a schedule runs, so it can be shown to the user.
+/// Every method does nothing by default.
+pub trait Renderer {
+    fn block_started(&mut self, _block: &Block) {}
+
+    /// Called a few times a second while a block is running.
+    fn tick(&mut self, _tick: &Tick) {}
+
+    /// Called once a block has only `before_end` left, for each of the warnings that were asked for.
+    fn block_ending(&mut self, _block: &Block, _before_end: Duration) {}
+
+    fn block_finished(&mut self, _block: &Block) {}
+
+    fn completed(&mut self) {}
+
+    fn aborted(&mut self) {}
+
+    /// Called once the computer wakes up after being asleep for `asleep` part way through a block, after `policy` has been applied.
+    fn woke(&mut self, _asleep: Duration, _policy: SleepPolicy) {}
+
+    /// Called whenever a block starts or a control is used, and every so often in between,
+    /// with everything needed to carry on from the same point if the app is closed.
+    fn checkpoint(&mut self, _block: &Block, _progress: &Progress) {}
+}
+
+/// Lets more than one renderer follow the same run, like the terminal and desktop notifications.
+impl<A: Renderer, B: Renderer> Renderer for (A, B) {
+    fn block_started(&mut self, block: &Block) {
+        self.0.block_started(block);
+        self.1.block_started(block);
+    }
+
+    fn tick(&mut self, tick: &Tick) {
+        self.0.tick(tick);
+        self.1.tick(tick);
+    }
+
+    fn block_ending(&mut self, block: &Block, before_end: Duration) {
+        self.0.block_ending(block, before_end);
+        self.1.block_ending(block, before_end);
+    }
+
+    fn block_finished(&mut self, block: &Block) {
+        self.0.block_finished(block);
+        self.1.block_finished(block);
+    }
+
+    fn completed(&mut self) {
+        self.0.completed();
+        self.1.completed();
+    }
+
+    fn aborted(&mut self) {
+        self.0.aborted();
+        self.1.aborted();
+    }
+
+    fn woke(&mut self, asleep: Duration, policy: SleepPolicy) {
+        self.0.woke(asleep, policy);
+        self.1.woke(asleep, policy);
+    }
+
+    fn checkpoint(&mut self, block: &Block, progress: &Progress) {
+        self.0.checkpoint(block, progress);
+        self.1.checkpoint(block, progress);
+    }
+}
+
+/// For renderers that are turned off in the settings or couldn't be set up.
+impl<R: Renderer> Renderer for Option<R> {
+    fn block_started(&mut self, block: &Block) {
+        if let Some(r) = self { r.block_started(block) }
+    }
+
+    fn tick(&mut self, tick: &Tick) {
+        if let Some(r) = self { r.tick(tick) }
+    }
+
+    fn block_ending(&mut self, block: &Block, before_end: Duration) {
+        if let Some(r) = self { r.block_ending(block, before_end) }
+    }
+
+    fn block_finished(&mut self, block: &Block) {
+        if let Some(r) = self { r.block_finished(block) }
+    }
+
+    fn completed(&mut self) {
+        if let Some(r) = self { r.completed() }
+    }
+
+    fn aborted(&mut self) {
+        if let Some(r) = self { r.aborted() }
+    }
+
+    fn woke(&mut self, asleep: Duration, policy: SleepPolicy) {
+        if let Some(r) = self { r.woke(asleep, policy) }
+    }
+
+    fn checkpoint(&mut self, block: &Block, progress: &Progress) {
+        if let Some(r) = self { r.checkpoint(block, progress) }
+    }
+}
+
+/// Plays a sound whenever a new block starts, one is about to end or the schedule is complete, for runs without a terminal.
+/// Sounds that can't be played are reported on stderr.
+pub struct SoundCues {
+    sounds: SoundSet,
+    player: SoundPlayer,
+    started_first_block: bool,
+}
+
+impl SoundCues {
+    pub fn new(sounds: SoundSet, player: SoundPlayer) -> SoundCues {
+        SoundCues { sounds, player, started_first_block: false }
+    }
+
+    fn play(&mut self, cue: Cue) {
+        if let Some(path) = self.sounds.path(cue) {
+            if let Err(e) = self.player.play(path, self.sounds.playback()) {
+                eprintln!("Sound failed to play: {e}");
+            }
+        }
+    }
+}
+
+impl Renderer for SoundCues {
+    fn block_started(&mut self, block: &Block) {
+        if self.started_first_block {
+            self.play(Cue::for_block(block.kind));
+        }
+
+        self.started_first_block = true;
+    }
+
+    fn tick(&mut self, _tick: &Tick) {
+        for e in self.player.take_errors() {
+            eprintln!("Sound failed to play: {e}");
+        }
+    }
+
+    fn block_ending(&mut self, _block: &Block, _before_end: Duration) {
+        self.play(Cue::Warning);
+    }
+
+    fn completed(&mut self) {
+        self.play(Cue::Complete);
+    }
+}
+
+/// How many of the blocks after the current one are worked out for the list of what's coming up.
+const UPCOMING_BLOCKS: usize = 20;
+
+/// Draws the running schedule full screen and plays a sound whenever a new block starts or the schedule is complete.
+pub struct TerminalRenderer<'a> {
+    schedule: &'a Schedule,
+    sounds: SoundSet,
+    player: SoundPlayer,
+    screen: Screen,
+    /// Why the last sound couldn't be played, shown above the key help until the next one plays.
+    sound_error: Option<String>,
+    /// Where hooks that fail are reported, with the last of them shown until the next block starts.
+    hook_errors: &'a Receiver<HookError>,
+    hook_error: Option<String>,
+    /// The position of the block that's being shown, which is None until the first tick.
+    position: Option<usize>,
+    /// Nothing is cued for the block the run starts in.
+    started_first_block: bool,
+    /// What's shown after the computer wakes up, with the policy that was applied.
+    woke: Option<(SleepPolicy, String)>,
+    /// None for schedules that never end.
+    total_blocks: Option<usize>,
+    header: String,
+    upcoming: Vec<Block>,
+    /// How long every block after the current one will take, if they ever end.
+    upcoming_duration: Option<Duration>,
+}
+
+impl<'a> TerminalRenderer<'a> {
+    /// Switches over to the full screen view until the renderer is dropped.
+    pub fn new(schedule: &'a Schedule, sounds: SoundSet, player: SoundPlayer, hook_errors: &'a Receiver<HookError>) -> TerminalRenderer<'a> {
+        TerminalRenderer {
+            schedule,
+            sounds,
+            player,
+            screen: Screen::enter(),
+            sound_error: None,
+            hook_errors,
+            hook_error: None,
+            position: None,
+            started_first_block: false,
+            woke: None,
+            total_blocks: schedule.total_blocks(),
+            header: String::new(),
+            upcoming: Vec::new(),
+            upcoming_duration: None,
+        }
+    }
+
+    fn play(&mut self, cue: Cue) {
+        if let Some(path) = self.sounds.path(cue) {
+            self.sound_error = self.player.play(path, self.sounds.playback()).err().map(|e| e.to_string());
+        }
+    }
+
+    fn header(&self, block: &Block) -> String {
+        if self.schedule.phases.is_some() {
+            return match block.kind {
+                BlockKind::Work => format!("{} (work block {})", block.label, block.index),
+                BlockKind::Rest | BlockKind::LongRest => block.label.clone(),
+            };
+        }
+
+        match block.kind {
+            BlockKind::Work => format!("Working block {}", block.index),
+            BlockKind::Rest => format!("Rest block {}", block.index),
+            BlockKind::LongRest => {
+                let blocks_per_long_rest = match self.schedule.rest_type {
+                    RestType::LongRest { blocks_per_long_rest, .. } => blocks_per_long_rest,
+                    RestType::Standard => block.index,
+                };
+
+                format!("Congratulations on completing {}{} blocks! Here's a deserved long break:",
+                    if block.index == blocks_per_long_rest {
+                        "your first "
+                    } else {
+                        "another "
+                    },
+                    blocks_per_long_rest,
+                )
+            }
+        }
+    }
+}
+
+impl Renderer for TerminalRenderer<'_> {
+    fn block_started(&mut self, block: &Block) {
+        //They've only just started the schedule themselves, so there's nothing to cue
+        if self.started_first_block {
+            self.play(Cue::for_block(block.kind));
+        }
+
+        self.started_first_block = true;
+        self.woke = None;
+        self.hook_error = None;
+    }
+
+    fn tick(&mut self, tick: &Tick) {
+        //Blocks can be passed without being started when time is counted all at once, so the position comes from the tick
+        if self.position != Some(tick.position) {
+            let next = tick.position + 1;
+
+            self.position = Some(tick.position);
+            self.header = self.header(tick.block);
+            self.upcoming = self.schedule.blocks().skip(next).take(UPCOMING_BLOCKS).collect();
+            self.upcoming_duration = self.total_blocks.map(|_| self.schedule.blocks().skip(next).map(|b| b.duration).sum());
+        }
+
+        //Pausing policies are answered by carrying on
+        if !tick.paused && matches!(self.woke, Some((SleepPolicy::Pause | SleepPolicy::Ask, _))) {
+            self.woke = None;
+        }
+
+        if let Some(e) = self.player.take_errors().pop() {
+            self.sound_error = Some(e.to_string());
+        }
+
+        if let Some(e) = self.hook_errors.try_iter().last() {
+            self.hook_error = Some(e.to_string());
+        }
+
+        let duration = tick.block.duration.as_secs_f64();
+        let block_progress = if duration > 0.0 {1.0 - tick.remaining.as_secs_f64() / duration} else {1.0};
+
+        let session_remaining = self.upcoming_duration.map(|upcoming| upcoming + tick.remaining);
+        let session_progress = session_remaining.map(|remaining| {
+            let total = (tick.elapsed + remaining).as_secs_f64();
+            if total > 0.0 {tick.elapsed.as_secs_f64() / total} else {1.0}
+        });
+
+        let message = match (&self.woke, &self.sound_error, &self.hook_error) {
+            (Some((_, message)), _, _) => Some(message.clone()),
+            (None, Some(e), _) => Some(format!("Sound failed to play: {e}")),
+            (None, None, Some(e)) => Some(format!("Hook failed: {e}")),
+            (None, None, None) => None,
+        };
+
+        self.screen.draw(&View {
+            title: &self.schedule.name,
+            position: match self.total_blocks {
+                Some(total) => format!("Block {} of {total}", tick.position + 1),
+                None => format!("Block {}", tick.position + 1),
+            },
+            header: &self.header,
+            remaining: round_up_to_second(tick.remaining),
+            paused: tick.paused,
+            block_progress,
+            session_progress,
+            elapsed: tick.elapsed,
+            session_remaining: session_remaining.map(round_up_to_second),
+            upcoming: &self.upcoming,
+            message: message.as_deref(),
+            footer: tick.status_line,
+        });
+    }
+
+    fn block_ending(&mut self, _block: &Block, _before_end: Duration) {
+        match self.sounds.get(Cue::Warning) {
+            //A soft tick from the terminal when there isn't a sound for it
+            None => console::bell(),
+            Some(CueSound::Silent) => (),
+            Some(CueSound::File(_)) => self.play(Cue::Warning),
+        }
+    }
+
+    fn woke(&mut self, asleep: Duration, policy: SleepPolicy) {
+        let asleep = format::dur_to_xhxmxs(asleep);
+
+        self.woke = Some((policy, match policy {
+            SleepPolicy::Count => format!("The computer was asleep for {asleep}, which has been counted."),
+            SleepPolicy::Pause => format!("Paused, as the computer was asleep for {asleep}. Press [space] to carry on."),
+            SleepPolicy::Ask => format!("The computer was asleep for {asleep}. Press [c] to count that time, or [space] to carry on from where you were."),
+        }));
+    }
+
+    fn completed(&mut self) {
+        self.play(Cue::Complete);
+
+        let message = self.sound_error.as_ref().map(|e| format!("Sound failed to play: {e}"));
+        let mut lines = vec!["Congratulations, you've completed your schedule! 🎉🎉🎉"];
+        lines.extend(message.as_deref());
+
+        self.screen.show(&lines);
+    }
+}
+
+/// The countdown should only hit 00:00 once the block is actually over.
+pub fn round_up_to_second(dur: Duration) -> Duration {
+    if dur.subsec_nanos() == 0 {
+        dur
+    } else {
+        Duration::from_secs(dur.as_secs() + 1)
+    }
+}
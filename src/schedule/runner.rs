@@ -0,0 +1,662 @@
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+use super::{clock::Clock, controls::{Control, Controls}, phase::Phases, render::Renderer, sleep::{SleepDetector, SleepPolicy}, warning::Warnings, Schedule};
+
+pub const QUARTER_SECOND: Duration = Duration::from_millis(250);
+pub const CONGRATS_TIME: Duration = Duration::from_millis(5000);
+/// How often the renderer is given a checkpoint while nothing else is happening.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The least a run waits for controls after a block changes, so blocks that are over as soon as they start
+/// can't keep it from drawing, taking input or giving the rest of the computer a turn.
+pub const TRANSITION_WAIT: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlockKind {
+    Work,
+    Rest,
+    LongRest,
+}
+
+/// A single stretch of the schedule.
+/// `index` is the number of the work block, counting from 1; rests share the index of the work block they follow (or 0 before any work).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub index: u32,
+    pub duration: Duration,
+    pub label: String,
+}
+
+impl Block {
+    /// What to call the block when listing it, like "Work block 3" or "Long rest".
+    pub fn name(&self) -> String {
+        match self.kind {
+            //Work/rest schedules label every work block the same, so they're told apart by number
+            BlockKind::Work if self.label == "Work" => format!("Work block {}", self.index),
+            BlockKind::Work => format!("{} (work block {})", self.label, self.index),
+            BlockKind::Rest | BlockKind::LongRest => self.label.clone(),
+        }
+    }
+}
+
+/// Iterates over the blocks of a schedule in the order they're run. Never ends for infinite schedules.
+pub struct Blocks {
+    phases: Phases,
+    work_blocks: u32,
+}
+
+impl Blocks {
+    pub fn new(schedule: &Schedule) -> Blocks {
+        Blocks {
+            phases: Phases::new(schedule.phases()),
+            work_blocks: 0,
+        }
+    }
+}
+
+impl Iterator for Blocks {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Block> {
+        let phase = self.phases.next()?;
+
+        if phase.kind == BlockKind::Work {
+            self.work_blocks += 1;
+        }
+
+        Some(Block { kind: phase.kind, index: self.work_blocks, duration: phase.duration, label: phase.label })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Finished,
+    Aborted,
+}
+
+/// How a run of a schedule went.
+#[derive(Debug, Clone, Copy)]
+pub struct RunSummary {
+    pub outcome: Outcome,
+    /// Work blocks that were run until the end, so skipped ones don't count.
+    pub work_blocks_completed: u32,
+    /// Time spent in work blocks while they weren't paused, including blocks that were skipped or aborted part way.
+    pub focused: Duration,
+}
+
+/// Where a running schedule is up to, given to the renderer a few times a second.
+pub struct Tick<'a> {
+    pub block: &'a Block,
+    /// How many blocks came before this one.
+    pub position: usize,
+    /// Left in the current block.
+    pub remaining: Duration,
+    pub paused: bool,
+    /// Time the schedule has been running for, not counting pauses.
+    pub elapsed: Duration,
+    /// What the controls want displayed, like the keys that can be pressed.
+    pub status_line: &'a str,
+}
+
+/// Where a run is up to, which is enough to carry on from the same point later.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    /// How many blocks came before the current one.
+    pub position: usize,
+    /// Left in the current block.
+    pub remaining: Duration,
+    pub paused: bool,
+    pub elapsed: Duration,
+    pub focused: Duration,
+    pub work_blocks_completed: u32,
+}
+
+/// Where a run would be after some time has passed without it, as worked out by `catch_up`.
+#[derive(Debug)]
+pub enum CaughtUp {
+    Running(Progress),
+    /// The schedule would have ended `after` into the time that passed.
+    Finished { summary: RunSummary, after: Duration },
+}
+
+pub enum Transition {
+    Next { finished: Block, started: Block },
+    Completed { finished: Block },
+}
+
+/// Keeps track of which block is running and when it ends.
+/// Doesn't sleep or read the clock itself: every method that depends on time takes the current instant.
+pub struct ScheduleRunner {
+    blocks: Blocks,
+    current: Block,
+    position: usize,
+    deadline: Instant,
+    //Time that was left in the block when it was paused, as the deadline is meaningless while paused
+    paused_remaining: Option<Duration>,
+    skipped: bool,
+    work_blocks_completed: u32,
+    focused: Duration,
+    elapsed: Duration,
+    //Focused and elapsed time have been counted up to here
+    counted_until: Instant,
+    warnings: Warnings,
+}
+
+impl ScheduleRunner {
+    /// Returns None if the schedule doesn't have any blocks to run.
+    /// `warnings` are how long before the end of each block to warn that it's ending.
+    pub fn new(schedule: &Schedule, warnings: &[Duration], now: Instant) -> Option<ScheduleRunner> {
+        let first = schedule.blocks().next()?;
+
+        ScheduleRunner::resume(schedule, warnings, &Progress { remaining: first.duration, ..Progress::default() }, now)
+    }
+
+    /// Carries on from `progress`, as if the run had never stopped. Returns None if the schedule doesn't have a block at its position.
+    pub fn resume(schedule: &Schedule, warnings: &[Duration], progress: &Progress, now: Instant) -> Option<ScheduleRunner> {
+        let mut blocks = schedule.blocks();
+        let current = blocks.nth(progress.position)?;
+
+        let mut warnings = Warnings::new(warnings);
+        warnings.rearm(progress.remaining);
+
+        Some(ScheduleRunner {
+            blocks,
+            deadline: now + progress.remaining,
+            current,
+            position: progress.position,
+            paused_remaining: progress.paused.then_some(progress.remaining),
+            skipped: false,
+            work_blocks_completed: progress.work_blocks_completed,
+            focused: progress.focused,
+            elapsed: progress.elapsed,
+            counted_until: now,
+            warnings,
+        })
+    }
+
+    pub fn current(&self) -> &Block {
+        &self.current
+    }
+
+    pub fn work_blocks_completed(&self) -> u32 {
+        self.work_blocks_completed
+    }
+
+    /// Time spent working so far.
+    pub fn focused(&self, now: Instant) -> Duration {
+        match self.current.kind {
+            BlockKind::Work => self.focused + self.uncounted(now),
+            _ => self.focused,
+        }
+    }
+
+    /// Time the schedule has been running for so far, not counting pauses.
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.elapsed + self.uncounted(now)
+    }
+
+    fn uncounted(&self, now: Instant) -> Duration {
+        if self.is_paused() {
+            return Duration::ZERO;
+        }
+
+        now.min(self.deadline).saturating_duration_since(self.counted_until)
+    }
+
+    /// Has to be called before anything changes the deadline or pauses the block, so the time so far is counted against the old state.
+    fn count_focus(&mut self, now: Instant) {
+        self.focused = self.focused(now);
+        self.elapsed = self.elapsed(now);
+        self.counted_until = now.min(self.deadline);
+    }
+
+    pub fn progress(&self, now: Instant) -> Progress {
+        Progress {
+            position: self.position,
+            remaining: self.remaining(now),
+            paused: self.is_paused(),
+            elapsed: self.elapsed(now),
+            focused: self.focused(now),
+            work_blocks_completed: self.work_blocks_completed,
+        }
+    }
+
+    fn summary(&self, outcome: Outcome, now: Instant) -> RunSummary {
+        RunSummary { outcome, work_blocks_completed: self.work_blocks_completed, focused: self.focused(now) }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_remaining.is_some()
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.paused_remaining.unwrap_or_else(|| self.deadline.saturating_duration_since(now))
+    }
+
+    /// Applies everything but `Control::Abort` and `Control::CountSleep`, which are up to whoever is running the schedule.
+    pub fn control(&mut self, control: &Control, now: Instant) {
+        self.count_focus(now);
+
+        match control {
+            Control::TogglePause => match self.paused_remaining.take() {
+                Some(left) => {
+                    self.deadline = now + left;
+                    self.counted_until = now;
+                }
+                None => self.paused_remaining = Some(self.deadline.saturating_duration_since(now)),
+            },
+            Control::Skip => {
+                self.paused_remaining = None;
+                self.deadline = now;
+                self.skipped = true;
+            }
+            Control::Restart => match &mut self.paused_remaining {
+                Some(left) => *left = self.current.duration,
+                None => self.deadline = now + self.current.duration,
+            },
+            Control::Extend(extra) => match &mut self.paused_remaining {
+                Some(left) => *left += *extra,
+                None => self.deadline += *extra,
+            },
+            Control::CountSleep | Control::Abort => (),
+        }
+
+        match control {
+            Control::Skip => self.warnings.disarm(),
+            _ => self.warnings.rearm(self.remaining(now)),
+        }
+    }
+
+    /// Counts `passed` as having gone by just before `now`, as if the timer had kept running through it, unpausing the block if it was paused.
+    /// Goes straight to whichever block that ends up in, so only the block it started in and the one it ends in are returned.
+    pub fn pass_time(&mut self, mut passed: Duration, now: Instant) -> Option<Transition> {
+        self.count_focus(now);
+
+        if let Some(left) = self.paused_remaining.take() {
+            self.deadline = now + left;
+        }
+
+        self.counted_until = now;
+        let (first, first_position) = (self.current.clone(), self.position);
+
+        loop {
+            let remaining = self.remaining(now);
+            let counted = passed.min(remaining);
+
+            self.elapsed += counted;
+
+            if self.current.kind == BlockKind::Work {
+                self.focused += counted;
+            }
+
+            if passed < remaining {
+                self.deadline = now + (remaining - passed);
+                break;
+            }
+
+            passed -= remaining;
+            self.deadline = now;
+            self.counted_until = now;
+
+            match self.advance(now)? {
+                Transition::Next { .. } => (),
+                Transition::Completed { finished } => return Some(Transition::Completed { finished }),
+            }
+        }
+
+        (self.position != first_position).then(|| Transition::Next { finished: first, started: self.current.clone() })
+    }
+
+    /// How long before the end of the block a warning is due, if one is. Each warning is only given once per block.
+    pub fn due_warning(&mut self, now: Instant) -> Option<Duration> {
+        if self.is_paused() {
+            return None;
+        }
+
+        self.warnings.due(self.remaining(now))
+    }
+
+    /// Moves on to the next block if the current one is over, returning what changed.
+    /// Once this returns `Transition::Completed` the runner is finished and shouldn't be advanced again.
+    pub fn advance(&mut self, now: Instant) -> Option<Transition> {
+        if self.is_paused() || !self.remaining(now).is_zero() {
+            return None;
+        }
+
+        //Also leaves the count at the deadline, which is when the next block starts however late this is called
+        self.count_focus(now);
+
+        if self.current.kind == BlockKind::Work && !self.skipped {
+            self.work_blocks_completed += 1;
+        }
+
+        self.skipped = false;
+
+        match self.blocks.next() {
+            Some(started) => {
+                //Chain off the old deadline rather than now, so time spent playing sounds and redrawing doesn't pile up
+                self.deadline += started.duration;
+                self.position += 1;
+                self.warnings.rearm(started.duration);
+                let finished = std::mem::replace(&mut self.current, started.clone());
+
+                Some(Transition::Next { finished, started })
+            }
+            None => Some(Transition::Completed { finished: self.current.clone() }),
+        }
+    }
+}
+
+/// Where a run that stopped at `progress` would be after another `passed`, had the timer kept going.
+/// Returns None if the schedule doesn't have a block at its position.
+pub fn catch_up(schedule: &Schedule, progress: &Progress, passed: Duration) -> Option<CaughtUp> {
+    let start = Instant::now();
+    let now = start + passed;
+    let mut runner = ScheduleRunner::resume(schedule, &[], progress, start)?;
+
+    loop {
+        match runner.advance(now) {
+            Some(Transition::Next { .. }) => (),
+            Some(Transition::Completed { .. }) => return Some(CaughtUp::Finished {
+                summary: runner.summary(Outcome::Finished, now),
+                after: runner.elapsed(now).saturating_sub(progress.elapsed),
+            }),
+            None => return Some(CaughtUp::Running(runner.progress(now))),
+        }
+    }
+}
+
+/// Tells `renderer` the block has changed, returning how the run went if it's over.
+fn report(transition: Transition, runner: &ScheduleRunner, now: Instant, clock: &impl Clock, renderer: &mut impl Renderer) -> Option<RunSummary> {
+    match transition {
+        Transition::Next { finished, started } => {
+            renderer.block_finished(&finished);
+            renderer.block_started(&started);
+            None
+        }
+        Transition::Completed { finished } => {
+            renderer.block_finished(&finished);
+            renderer.completed();
+            clock.sleep(CONGRATS_TIME);
+            Some(runner.summary(Outcome::Finished, now))
+        }
+    }
+}
+
+/// Runs the schedule to completion (or until aborted), timed by `clock`, taking input from `controls` and reporting to `renderer`.
+/// `warnings` are how long before the end of each block the renderer is told it's ending, and `on_sleep` is what happens
+/// if the computer goes to sleep part way through. Starts from the beginning unless given the `progress` of an earlier run to carry on from.
+pub fn run(schedule: &Schedule, warnings: &[Duration], on_sleep: SleepPolicy, progress: Option<&Progress>, clock: &impl Clock, controls: &mut impl Controls, renderer: &mut impl Renderer) -> RunSummary {
+    let runner = match progress {
+        Some(progress) => ScheduleRunner::resume(schedule, warnings, progress, clock.now()),
+        None => ScheduleRunner::new(schedule, warnings, clock.now()),
+    };
+
+    let Some(mut runner) = runner else {
+        return RunSummary { outcome: Outcome::Finished, work_blocks_completed: 0, focused: Duration::ZERO };
+    };
+
+    renderer.block_started(runner.current());
+    //None whenever something has changed that should be checkpointed straight away
+    let mut last_checkpoint: Option<Instant> = None;
+    let mut sleep = SleepDetector::new(clock.now(), clock.wall());
+    //How long the computer was asleep for, while they're being asked whether that should count
+    let mut unanswered_sleep: Option<Duration> = None;
+
+    loop {
+        let now = clock.now();
+        let mut transitioned = false;
+
+        //Paused blocks weren't going anywhere anyway
+        if let Some(asleep) = sleep.check(now, clock.wall()).filter(|_| !runner.is_paused()) {
+            let transition = match on_sleep {
+                SleepPolicy::Count => runner.pass_time(asleep, now),
+                SleepPolicy::Pause | SleepPolicy::Ask => {
+                    runner.control(&Control::TogglePause, now);
+                    None
+                }
+            };
+
+            if let Some(summary) = transition.and_then(|transition| report(transition, &runner, now, clock, renderer)) {
+                return summary;
+            }
+
+            unanswered_sleep = (on_sleep == SleepPolicy::Ask).then_some(asleep);
+            renderer.woke(asleep, on_sleep);
+            last_checkpoint = None;
+        }
+
+        if let Some(transition) = runner.advance(now) {
+            if let Some(summary) = report(transition, &runner, now, clock, renderer) {
+                return summary;
+            }
+
+            last_checkpoint = None;
+            transitioned = true;
+        }
+
+        if last_checkpoint.is_none_or(|at| now.saturating_duration_since(at) >= CHECKPOINT_INTERVAL) {
+            renderer.checkpoint(runner.current(), &runner.progress(now));
+            last_checkpoint = Some(now);
+        }
+
+        if let Some(before_end) = runner.due_warning(now) {
+            renderer.block_ending(runner.current(), before_end);
+        }
+
+        let remaining = runner.remaining(now);
+        renderer.tick(&Tick {
+            block: runner.current(),
+            position: runner.position,
+            remaining,
+            paused: runner.is_paused(),
+            elapsed: runner.elapsed(now),
+            status_line: &controls.status_line(),
+        });
+
+        let wait = if runner.is_paused() {QUARTER_SECOND} else {remaining.min(QUARTER_SECOND)};
+        let wait = if transitioned {wait.max(TRANSITION_WAIT)} else {wait};
+
+        if let Some(control) = controls.poll(wait) {
+            let now = clock.now();
+            last_checkpoint = None;
+
+            match control {
+                Control::Abort => {
+                    renderer.aborted();
+                    return runner.summary(Outcome::Aborted, now);
+                }
+                Control::CountSleep => {
+                    let transition = unanswered_sleep.take().and_then(|asleep| runner.pass_time(asleep, now));
+
+                    if let Some(summary) = transition.and_then(|transition| report(transition, &runner, now, clock, renderer)) {
+                        return summary;
+                    }
+                }
+                control => {
+                    runner.control(&control, now);
+                    unanswered_sleep = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::{phase, RepeatType, RestType};
+
+    const MIN: Duration = Duration::from_secs(60);
+
+    fn schedule(repeat_type: RepeatType, rest_type: RestType) -> Schedule {
+        Schedule {
+            name: String::from("test"),
+            work_duration: 25 * MIN,
+            rest_duration: 5 * MIN,
+            repeat_type,
+            rest_type,
+            phases: None,
+            sounds: Default::default(),
+            warnings: None,
+            hooks: Default::default(),
+        }
+    }
+
+    fn kinds(schedule: &Schedule, take: usize) -> Vec<(BlockKind, u32)> {
+        schedule.blocks().take(take).map(|b| (b.kind, b.index)).collect()
+    }
+
+    #[test]
+    fn long_rests_should_follow_every_nth_work_block() {
+        let schedule = Schedule::pomodoro();
+        let blocks = kinds(&schedule, 100);
+
+        assert_eq!(blocks.len(), 15, "8 work blocks and the 7 rests between them");
+        assert_eq!(blocks.first(), Some(&(BlockKind::Work, 1)));
+        assert_eq!(blocks.last(), Some(&(BlockKind::Work, 8)));
+
+        let long_rests: Vec<u32> = blocks.iter().filter(|(k, _)| *k == BlockKind::LongRest).map(|(_, i)| *i).collect();
+        assert_eq!(long_rests, vec![4]);
+    }
+
+    #[test]
+    fn blocks_should_add_up_to_total_duration() {
+        let schedule = Schedule::pomodoro();
+        let total: Duration = schedule.blocks().map(|b| b.duration).sum();
+
+        assert_eq!(Some(total), schedule.get_total_duration());
+    }
+
+    #[test]
+    fn finite_schedules_should_end_and_infinite_ones_should_not() {
+        assert_eq!(kinds(&schedule(RepeatType::Finite(1), RestType::Standard), 10), vec![(BlockKind::Work, 1)]);
+        assert!(kinds(&schedule(RepeatType::Finite(0), RestType::Standard), 10).is_empty());
+        assert_eq!(kinds(&schedule(RepeatType::Infinite, RestType::Standard), 1000).len(), 1000);
+    }
+
+    #[test]
+    fn phases_should_number_work_blocks_and_old_schedules_should_still_load() {
+        let old = r#"{"name":"Old","work_duration":{"secs":1500,"nanos":0},"rest_duration":{"secs":300,"nanos":0},"repeat_type":{"Finite":2},"rest_type":"Standard"}"#;
+        let old: Schedule = serde_json::from_str(old).unwrap();
+        assert_eq!(kinds(&old, 10), vec![(BlockKind::Work, 1), (BlockKind::Rest, 1), (BlockKind::Work, 2)]);
+
+        let schedule = Schedule::from_phases(String::from("test"), phase::parse("warm-up 10:00 rest, 2x(work 50:00, break 10:00 rest), lunch 1:00:00 long-rest").unwrap());
+        assert_eq!(kinds(&schedule, 10), vec![
+            (BlockKind::Rest, 0),
+            (BlockKind::Work, 1),
+            (BlockKind::Rest, 1),
+            (BlockKind::Work, 2),
+            (BlockKind::Rest, 2),
+            (BlockKind::LongRest, 2),
+        ]);
+        assert_eq!(schedule.get_total_duration(), Some(190 * MIN));
+    }
+
+    #[test]
+    fn runner_should_pause_extend_and_skip() {
+        let schedule = schedule(RepeatType::Finite(2), RestType::Standard);
+        let start = Instant::now();
+        let mut runner = ScheduleRunner::new(&schedule, &[], start).unwrap();
+
+        runner.control(&Control::TogglePause, start + MIN);
+        assert!(runner.advance(start + 60 * MIN).is_none(), "paused blocks should never end");
+        assert_eq!(runner.remaining(start + 60 * MIN), 24 * MIN);
+
+        runner.control(&Control::TogglePause, start + 60 * MIN);
+        runner.control(&Control::Extend(MIN), start + 60 * MIN);
+        assert_eq!(runner.remaining(start + 60 * MIN), 25 * MIN);
+
+        runner.control(&Control::Skip, start + 61 * MIN);
+        assert!(matches!(runner.advance(start + 61 * MIN), Some(Transition::Next { started: Block { kind: BlockKind::Rest, .. }, .. })));
+        assert!(matches!(runner.advance(start + 66 * MIN), Some(Transition::Next { started: Block { kind: BlockKind::Work, index: 2, .. }, .. })));
+        assert!(matches!(runner.advance(start + 91 * MIN), Some(Transition::Completed { .. })));
+        assert_eq!(runner.work_blocks_completed(), 1, "the skipped block shouldn't count");
+        //1 minute before pausing, 1 after, and all 25 of the second work block
+        assert_eq!(runner.focused(start + 91 * MIN), 27 * MIN);
+        //The same, plus the 5 minute rest
+        assert_eq!(runner.elapsed(start + 91 * MIN), 32 * MIN);
+    }
+
+    #[test]
+    fn warnings_should_be_given_once_unless_the_block_is_restarted() {
+        let schedule = schedule(RepeatType::Finite(2), RestType::Standard);
+        let start = Instant::now();
+        let sec = Duration::from_secs(1);
+        //The rests are only 5 minutes long, so they never get the 10 minute warning
+        let mut runner = ScheduleRunner::new(&schedule, &[10 * MIN, 60 * sec, 10 * sec], start).unwrap();
+
+        assert_eq!(runner.due_warning(start + 14 * MIN), None);
+        assert_eq!(runner.due_warning(start + 15 * MIN), Some(10 * MIN));
+        assert_eq!(runner.due_warning(start + 15 * MIN), None, "already given");
+        assert_eq!(runner.due_warning(start + 24 * MIN + 55 * sec), Some(10 * sec), "the 60s warning was passed too, but only the latest is given");
+
+        runner.control(&Control::Restart, start + 24 * MIN + 55 * sec);
+        runner.control(&Control::TogglePause, start + 40 * MIN);
+        assert_eq!(runner.due_warning(start + 60 * MIN), None, "paused blocks don't get closer to ending");
+        runner.control(&Control::TogglePause, start + 60 * MIN);
+        assert_eq!(runner.due_warning(start + 60 * MIN), Some(10 * MIN), "restarting should give every warning again");
+
+        runner.control(&Control::Skip, start + 61 * MIN);
+        assert_eq!(runner.due_warning(start + 61 * MIN), None, "skipped blocks end without a warning");
+        assert!(runner.advance(start + 61 * MIN).is_some());
+
+        assert_eq!(runner.due_warning(start + 65 * MIN), Some(60 * sec));
+    }
+
+    #[test]
+    fn resumed_runs_should_carry_on_from_the_same_point() {
+        let schedule = schedule(RepeatType::Finite(2), RestType::Standard);
+        let start = Instant::now();
+        let mut runner = ScheduleRunner::new(&schedule, &[], start).unwrap();
+
+        assert!(runner.advance(start + 25 * MIN).is_some());
+        let progress = runner.progress(start + 27 * MIN);
+        assert_eq!(progress, Progress { position: 1, remaining: 3 * MIN, paused: false, elapsed: 27 * MIN, focused: 25 * MIN, work_blocks_completed: 1 });
+
+        let later = start + 600 * MIN;
+        let resumed = ScheduleRunner::resume(&schedule, &[], &progress, later).unwrap();
+        assert_eq!(resumed.current().kind, BlockKind::Rest);
+        assert_eq!(resumed.progress(later), progress, "the time in between shouldn't count");
+
+        //3 minutes to finish the rest, then 7 into the last work block
+        assert!(matches!(catch_up(&schedule, &progress, 10 * MIN),
+            Some(CaughtUp::Running(Progress { position: 2, remaining, elapsed, focused, work_blocks_completed: 1, .. }))
+                if remaining == 18 * MIN && elapsed == 37 * MIN && focused == 32 * MIN));
+
+        match catch_up(&schedule, &progress, 60 * MIN) {
+            Some(CaughtUp::Finished { summary, after }) => {
+                assert_eq!(after, 28 * MIN);
+                assert_eq!(summary.work_blocks_completed, 2);
+            }
+            other => panic!("should have finished while away, got {other:?}"),
+        }
+
+        let paused = Progress { paused: true, ..progress.clone() };
+        assert!(matches!(catch_up(&schedule, &paused, 60 * MIN), Some(CaughtUp::Running(p)) if p == paused), "paused runs shouldn't move on");
+        assert!(catch_up(&schedule, &Progress { position: 3, ..progress }, MIN).is_none(), "there's no fourth block");
+    }
+
+    #[test]
+    fn passing_time_should_go_straight_to_the_block_it_ends_up_in() {
+        let schedule = schedule(RepeatType::Finite(2), RestType::Standard);
+        let start = Instant::now();
+        let mut runner = ScheduleRunner::new(&schedule, &[], start).unwrap();
+
+        runner.control(&Control::TogglePause, start + 10 * MIN);
+        assert!(runner.pass_time(5 * MIN, start + 10 * MIN).is_none(), "still in the first block");
+        assert!(!runner.is_paused());
+        assert_eq!(runner.remaining(start + 10 * MIN), 10 * MIN);
+
+        //Through the rest and 5 minutes into the second work block
+        let Some(Transition::Next { finished, started }) = runner.pass_time(20 * MIN, start + 10 * MIN) else { panic!("should have moved on") };
+        assert_eq!((finished.kind, finished.index), (BlockKind::Work, 1));
+        assert_eq!((started.kind, started.index), (BlockKind::Work, 2));
+        assert_eq!(runner.progress(start + 10 * MIN), Progress { position: 2, remaining: 20 * MIN, paused: false, elapsed: 35 * MIN, focused: 30 * MIN, work_blocks_completed: 1 });
+
+        assert!(matches!(runner.pass_time(60 * MIN, start + 11 * MIN), Some(Transition::Completed { .. })));
+        assert_eq!(runner.work_blocks_completed(), 2);
+    }
+}
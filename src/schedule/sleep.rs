@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Serialize, Deserialize};
+
+/// The real clock has to get this far ahead of the monotonic one before it's taken as the computer having been asleep,
+/// so small corrections to the system time don't count.
+pub const SLEEP_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// What to do about the time a computer spent asleep (suspended) part way through a block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SleepPolicy {
+    /// Counts the time as having passed, as if the timer had kept running.
+    #[default]
+    Count,
+    /// Pauses the block where it was when the computer went to sleep.
+    Pause,
+    /// Pauses the block, and asks whether the time should count.
+    Ask,
+}
+
+impl SleepPolicy {
+    pub const ALL: [SleepPolicy; 3] = [SleepPolicy::Count, SleepPolicy::Pause, SleepPolicy::Ask];
+
+    /// How the policy is named in the settings.
+    pub fn setting_name(self) -> &'static str {
+        match self {
+            SleepPolicy::Count => "count",
+            SleepPolicy::Pause => "pause",
+            SleepPolicy::Ask => "ask",
+        }
+    }
+
+    pub fn from_setting_name(name: &str) -> Option<SleepPolicy> {
+        SleepPolicy::ALL.into_iter().find(|policy| policy.setting_name().eq_ignore_ascii_case(name))
+    }
+
+    /// Finishes the sentence "When the computer wakes up...".
+    pub fn describe(self) -> &'static str {
+        match self {
+            SleepPolicy::Count => "the time it was asleep counts, as if the timer had kept running",
+            SleepPolicy::Pause => "the block is paused where it was when the computer went to sleep",
+            SleepPolicy::Ask => "the block is paused, and you're asked whether the time it was asleep should count",
+        }
+    }
+}
+
+/// Notices the computer having been asleep by comparing the monotonic clock, which stops while it's asleep, with the real one, which doesn't.
+pub struct SleepDetector {
+    last: (Instant, SystemTime),
+}
+
+impl SleepDetector {
+    pub fn new(now: Instant, wall: SystemTime) -> SleepDetector {
+        SleepDetector { last: (now, wall) }
+    }
+
+    /// How long the computer was asleep since the last check, if it was.
+    pub fn check(&mut self, now: Instant, wall: SystemTime) -> Option<Duration> {
+        let (last_now, last_wall) = std::mem::replace(&mut self.last, (now, wall));
+
+        //The real clock going backwards is someone changing it, not sleep
+        let wall_passed = wall.duration_since(last_wall).ok()?;
+        let asleep = wall_passed.saturating_sub(now.saturating_duration_since(last_now));
+
+        (asleep >= SLEEP_THRESHOLD).then_some(asleep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_big_jumps_in_the_real_clock_should_count_as_sleep() {
+        let (now, wall) = (Instant::now(), SystemTime::now());
+        let mut detector = SleepDetector::new(now, wall);
+        let sec = Duration::from_secs(1);
+
+        assert_eq!(detector.check(now + sec, wall + sec), None);
+        assert_eq!(detector.check(now + 2 * sec, wall + 4 * sec), None, "small corrections aren't sleep");
+        assert_eq!(detector.check(now + 3 * sec, wall + 3605 * sec), Some(3600 * sec));
+        assert_eq!(detector.check(now + 4 * sec, wall + 3606 * sec), None, "each sleep should only be noticed once");
+        assert_eq!(detector.check(now + 5 * sec, wall), None, "the clock being set back isn't sleep");
+    }
+}
@@ -0,0 +1,286 @@
+use std::{io::{self, Write}, panic, sync::{atomic::{AtomicBool, Ordering}, Once}, time::Duration};
+
+use crossterm::{cursor, queue, terminal};
+
+use super::{format, runner::Block};
+
+/// Used when the terminal's size can't be read, like when the output isn't a terminal.
+const FALLBACK_SIZE: (u16, u16) = (80, 24);
+
+/// How tall the big digits are.
+const DIGIT_ROWS: usize = 5;
+
+/// Big digits for the countdown, each 5 wide apart from the colon.
+fn big_glyph(c: char) -> [&'static str; DIGIT_ROWS] {
+    match c {
+        '0' => ["█████", "█   █", "█   █", "█   █", "█████"],
+        '1' => ["  █  ", " ██  ", "  █  ", "  █  ", " ███ "],
+        '2' => ["█████", "    █", "█████", "█    ", "█████"],
+        '3' => ["█████", "    █", " ████", "    █", "█████"],
+        '4' => ["█   █", "█   █", "█████", "    █", "    █"],
+        '5' => ["█████", "█    ", "█████", "    █", "█████"],
+        '6' => ["█████", "█    ", "█████", "█   █", "█████"],
+        '7' => ["█████", "    █", "   █ ", "  █  ", "  █  "],
+        '8' => ["█████", "█   █", "█████", "█   █", "█████"],
+        '9' => ["█████", "█   █", "█████", "    █", "█████"],
+        ':' => [" ", "█", " ", "█", " "],
+        _ => ["     "; DIGIT_ROWS],
+    }
+}
+
+/// `text` drawn in big digits, one string per row.
+pub fn big_text(text: &str) -> Vec<String> {
+    (0..DIGIT_ROWS)
+        .map(|row| text.chars().map(|c| big_glyph(c)[row]).collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+/// A bar `width` characters wide, filled up to `progress`, from 0 to 1.
+pub fn progress_bar(progress: f64, width: usize) -> String {
+    let filled = ((progress.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+fn centre(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(char_len(text)) / 2;
+
+    truncate(&format!("{}{text}", " ".repeat(padding)), width)
+}
+
+/// A labelled bar with its percentage, filling the width.
+fn labelled_bar(label: &str, progress: f64, width: usize) -> String {
+    let percent = format!(" {:>3.0}%", progress.clamp(0.0, 1.0) * 100.0);
+    let bar_width = width.saturating_sub(label.len() + percent.len() + 1);
+
+    format!("{label} {}{percent}", progress_bar(progress, bar_width))
+}
+
+/// Everything shown while a schedule runs.
+pub struct View<'a> {
+    pub title: &'a str,
+    /// Which block this is, like "Block 3 of 15".
+    pub position: String,
+    pub header: &'a str,
+    pub remaining: Duration,
+    pub paused: bool,
+    pub block_progress: f64,
+    /// None for schedules that go on until they're quit.
+    pub session_progress: Option<f64>,
+    pub elapsed: Duration,
+    pub session_remaining: Option<Duration>,
+    pub upcoming: &'a [Block],
+    /// Something that went wrong, like a sound not playing.
+    pub message: Option<&'a str>,
+    pub footer: &'a str,
+}
+
+/// The lines of the screen for `view`, each at most `width` characters and no more than `height` of them.
+/// Upcoming blocks are the first to go when there isn't room, then anything below the countdown.
+pub fn layout(view: &View, width: usize, height: usize) -> Vec<String> {
+    let mut top = Vec::new();
+
+    let title_room = width.saturating_sub(char_len(&view.position) + 1);
+    let title = truncate(view.title, title_room);
+    top.push(format!("{title}{}{}", " ".repeat(width.saturating_sub(char_len(&title) + char_len(&view.position))), view.position));
+    top.push(String::new());
+    top.push(view.header.to_string());
+    top.push(String::new());
+
+    let countdown = format::dur_to_hhmmss(view.remaining);
+    let big = big_text(&countdown);
+
+    if big.iter().all(|row| char_len(row) <= width) && height >= 16 {
+        top.extend(big.iter().map(|row| centre(row, width)));
+    } else {
+        top.push(centre(&countdown, width));
+    }
+
+    top.push(centre(if view.paused {"PAUSED"} else {""}, width));
+    top.push(String::new());
+    top.push(labelled_bar("Block  ", view.block_progress, width));
+
+    if let Some(progress) = view.session_progress {
+        top.push(labelled_bar("Session", progress, width));
+    }
+
+    top.push(match view.session_remaining {
+        Some(remaining) => format!("Elapsed {}   Remaining {}", format::dur_to_hhmmss(view.elapsed), format::dur_to_hhmmss(remaining)),
+        None => format!("Elapsed {}", format::dur_to_hhmmss(view.elapsed)),
+    });
+
+    let mut bottom = Vec::new();
+
+    if let Some(message) = view.message {
+        bottom.push(message.to_string());
+    }
+
+    bottom.push(view.footer.to_string());
+
+    //Upcoming blocks fill whatever's left, with a blank line either side
+    let room = height.saturating_sub(top.len() + bottom.len() + 3);
+
+    if room > 0 && !view.upcoming.is_empty() {
+        top.push(String::new());
+        top.push(String::from("Up next:"));
+
+        for block in view.upcoming.iter().take(room) {
+            let duration = format::dur_to_xhxmxs(block.duration);
+            let name_room = width.saturating_sub(duration.len() + 3);
+            let name = truncate(&block.name(), name_room);
+
+            top.push(format!("  {name}{}{duration}", " ".repeat(width.saturating_sub(char_len(&name) + duration.len() + 2))));
+        }
+    }
+
+    top.truncate(height.saturating_sub(bottom.len()));
+
+    let mut lines = top;
+    lines.resize(height.saturating_sub(bottom.len()), String::new());
+    lines.extend(bottom);
+    lines.truncate(height);
+
+    lines.into_iter().map(|line| truncate(&line, width)).collect()
+}
+
+/// Set while the alternate screen is in use, so a panic knows to put the terminal back first.
+static SCREEN_ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+fn restore_terminal() {
+    let mut stdout = io::stdout();
+    queue!(stdout, cursor::Show, terminal::LeaveAlternateScreen).ok();
+    stdout.flush().ok();
+    terminal::disable_raw_mode().ok();
+}
+
+/// The alternate screen in raw mode, put back the way it was when dropped, or if anything panics while it's in use.
+pub struct Screen {
+    size: (u16, u16),
+}
+
+impl Screen {
+    pub fn enter() -> Screen {
+        PANIC_HOOK.call_once(|| {
+            let previous = panic::take_hook();
+
+            panic::set_hook(Box::new(move |info| {
+                //Otherwise the message would be printed on the alternate screen and lost with it
+                if SCREEN_ACTIVE.swap(false, Ordering::SeqCst) {
+                    restore_terminal();
+                }
+
+                previous(info);
+            }));
+        });
+
+        //Without a terminal, like in tests, there's nothing to switch, but drawing still works
+        terminal::enable_raw_mode().ok();
+        let mut stdout = io::stdout();
+        queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide).ok();
+        stdout.flush().ok();
+        SCREEN_ACTIVE.store(true, Ordering::SeqCst);
+
+        Screen { size: (0, 0) }
+    }
+
+    /// Draws every line over what was there before. Everything is cleared first if the terminal has been resized.
+    pub fn draw(&mut self, view: &View) {
+        let size = terminal::size().unwrap_or(FALLBACK_SIZE);
+        let mut stdout = io::stdout();
+
+        if size != self.size {
+            self.size = size;
+            queue!(stdout, terminal::Clear(terminal::ClearType::All)).ok();
+        }
+
+        for (row, line) in layout(view, usize::from(size.0), usize::from(size.1)).iter().enumerate() {
+            queue!(stdout, cursor::MoveTo(0, row as u16)).ok();
+            write!(stdout, "{line}").ok();
+            queue!(stdout, terminal::Clear(terminal::ClearType::UntilNewLine)).ok();
+        }
+
+        stdout.flush().ok();
+    }
+
+    /// Replaces the whole screen with `lines`, for messages like the one at the end.
+    pub fn show(&mut self, lines: &[&str]) {
+        let mut stdout = io::stdout();
+        queue!(stdout, terminal::Clear(terminal::ClearType::All)).ok();
+
+        for (row, line) in lines.iter().enumerate() {
+            queue!(stdout, cursor::MoveTo(0, row as u16)).ok();
+            write!(stdout, "{line}").ok();
+        }
+
+        stdout.flush().ok();
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        if SCREEN_ACTIVE.swap(false, Ordering::SeqCst) {
+            restore_terminal();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::Schedule;
+
+    fn view<'a>(upcoming: &'a [Block], message: Option<&'a str>) -> View<'a> {
+        View {
+            title: "Pomodoro",
+            position: String::from("Block 3 of 15"),
+            header: "Working block 2",
+            remaining: Duration::from_secs(12 * 60 + 34),
+            paused: true,
+            block_progress: 0.5,
+            session_progress: Some(0.25),
+            elapsed: Duration::from_secs(42 * 60),
+            session_remaining: Some(Duration::from_secs(2 * 3600)),
+            upcoming,
+            message,
+            footer: "[q] quit",
+        }
+    }
+
+    #[test]
+    fn layout_should_fit_the_terminal_whatever_its_size() {
+        let upcoming: Vec<Block> = Schedule::pomodoro().blocks().collect();
+
+        let lines = layout(&view(&upcoming, Some("Sound failed to play")), 80, 24);
+        assert_eq!(lines.len(), 24);
+        assert_eq!(lines[0], format!("Pomodoro{}Block 3 of 15", " ".repeat(80 - 8 - 13)));
+        assert!(lines.iter().any(|line| line.contains(&big_text("12:34")[0])), "{lines:#?}");
+        assert!(lines.iter().any(|line| line.trim() == "PAUSED"));
+        assert!(lines.iter().any(|line| line.starts_with("Session ████") && line.ends_with(" 25%")));
+        assert!(lines.iter().any(|line| line.starts_with("  Work block 1")));
+        assert_eq!(lines[22], "Sound failed to play");
+        assert_eq!(lines[23], "[q] quit");
+
+        for (width, height) in [(20, 6), (1, 1), (0, 0), (200, 60)] {
+            let lines = layout(&view(&upcoming, None), width, height);
+
+            assert_eq!(lines.len(), height);
+            assert!(lines.iter().all(|line| line.chars().count() <= width), "{lines:#?}");
+
+            if height > 0 {
+                assert_eq!(lines.last().unwrap(), &truncate("[q] quit", width), "the key help should always be shown");
+            }
+        }
+
+        let small = layout(&view(&upcoming, None), 20, 6);
+        assert!(small.iter().any(|line| line.contains("12:34")), "too small for big digits: {small:#?}");
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use super::format;
+
+/// Reads a comma separated list of how long before the end of a block to warn, like "1:00, 10".
+/// The warnings come back latest first, without duplicates.
+pub fn parse(list: &str) -> Result<Vec<Duration>, String> {
+    let mut warnings = Vec::new();
+
+    for item in list.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        match format::try_hhmmss_to_dur(item) {
+            Some(dur) if !dur.is_zero() => warnings.push(dur),
+            _ => return Err(format!("'{item}' isn't a time before the end of a block, like 1:00 or 10")),
+        }
+    }
+
+    warnings.sort_by(|a, b| b.cmp(a));
+    warnings.dedup();
+
+    Ok(warnings)
+}
+
+pub fn to_list(warnings: &[Duration]) -> String {
+    if warnings.is_empty() {
+        return String::from("none");
+    }
+
+    warnings.iter().map(|w| format::dur_to_xhxmxs(*w)).collect::<Vec<_>>().join(", ")
+}
+
+/// A sentence saying when the warnings are given.
+pub fn describe(warnings: &[Duration]) -> String {
+    if warnings.is_empty() {
+        String::from("You won't be warned before blocks end.")
+    } else {
+        format!("You'll be warned {} before the end of each block.", to_list(warnings))
+    }
+}
+
+/// Keeps track of which warnings have been given for the block that's running.
+pub struct Warnings {
+    /// Latest first.
+    before_end: Vec<Duration>,
+    armed: Vec<bool>,
+}
+
+impl Warnings {
+    pub fn new(before_end: &[Duration]) -> Warnings {
+        let mut before_end = before_end.to_vec();
+        before_end.sort_by(|a, b| b.cmp(a));
+        before_end.dedup();
+
+        Warnings { armed: vec![false; before_end.len()], before_end }
+    }
+
+    /// Arms every warning that's still to come with `remaining` left, so it's given again if the block was restarted or extended.
+    /// Warnings as long as the block itself are never armed, as they'd go off the moment it started.
+    pub fn rearm(&mut self, remaining: Duration) {
+        for (armed, warning) in self.armed.iter_mut().zip(&self.before_end) {
+            *armed |= *warning < remaining;
+        }
+    }
+
+    pub fn disarm(&mut self) {
+        self.armed.fill(false);
+    }
+
+    /// The warning that's due with `remaining` left, if any. Warnings that were passed all at once are given as one.
+    pub fn due(&mut self, remaining: Duration) -> Option<Duration> {
+        //The block is over, which says more than any warning could
+        if remaining.is_zero() {
+            self.disarm();
+            return None;
+        }
+
+        let mut due = None;
+
+        for (armed, warning) in self.armed.iter_mut().zip(&self.before_end) {
+            if *armed && remaining <= *warning {
+                *armed = false;
+                due = Some(*warning);
+            }
+        }
+
+        due
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use crate::schedule::runner::BlockKind;
+use super::Playback;
+
+/// Sounds can't be repeated more than this many times in a row, so a typo can't leave one playing for ages.
+pub const MAX_REPEAT: u32 = 10;
+
+/// The moments in a run that can have their own sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    WorkStart,
+    RestStart,
+    LongRestStart,
+    Complete,
+    /// A block is about to end.
+    Warning,
+}
+
+impl Cue {
+    pub const ALL: [Cue; 5] = [Cue::WorkStart, Cue::RestStart, Cue::LongRestStart, Cue::Complete, Cue::Warning];
+
+    /// Whether the cue is for moving on to something new, rather than warning about it.
+    pub fn is_transition(self) -> bool {
+        self != Cue::Warning
+    }
+
+    pub fn for_block(kind: BlockKind) -> Cue {
+        match kind {
+            BlockKind::Work => Cue::WorkStart,
+            BlockKind::Rest => Cue::RestStart,
+            BlockKind::LongRest => Cue::LongRestStart,
+        }
+    }
+
+    /// Finishes the sentence "Played when...".
+    pub fn describe(self) -> &'static str {
+        match self {
+            Cue::WorkStart => "work starts",
+            Cue::RestStart => "a rest starts",
+            Cue::LongRestStart => "a long rest starts",
+            Cue::Complete => "the schedule is complete",
+            Cue::Warning => "a block is about to end",
+        }
+    }
+
+    /// How the cue's sound is named on the command line.
+    pub fn setting_name(self) -> &'static str {
+        match self {
+            Cue::WorkStart => "work-start-sound",
+            Cue::RestStart => "rest-start-sound",
+            Cue::LongRestStart => "long-rest-start-sound",
+            Cue::Complete => "complete-sound",
+            Cue::Warning => "warning-sound",
+        }
+    }
+
+    pub fn from_setting_name(name: &str) -> Option<Cue> {
+        Cue::ALL.into_iter().find(|cue| cue.setting_name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CueSound {
+    /// Plays nothing, even if there's a sound it would otherwise fall back on.
+    Silent,
+    File(String),
+}
+
+/// Which sound to play for each cue, and how. Anything left as None falls back on another set of sounds,
+/// so schedules only have to set the sounds they want to be different from the app's.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoundSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_start: Option<CueSound>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest_start: Option<CueSound>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_rest_start: Option<CueSound>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complete: Option<CueSound>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<CueSound>,
+
+    /// A percentage, from 0 to 100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<u8>,
+    /// How many times each sound is played back to back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<u32>,
+}
+
+impl SoundSet {
+    /// Every transition playing the same sound, like the single sound older versions had.
+    pub fn all_transitions(sound: CueSound) -> SoundSet {
+        let mut sounds = SoundSet::default();
+
+        for cue in Cue::ALL.into_iter().filter(|cue| cue.is_transition()) {
+            sounds.set(cue, Some(sound.clone()));
+        }
+
+        sounds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == SoundSet::default()
+    }
+
+    pub fn get(&self, cue: Cue) -> Option<&CueSound> {
+        match cue {
+            Cue::WorkStart => self.work_start.as_ref(),
+            Cue::RestStart => self.rest_start.as_ref(),
+            Cue::LongRestStart => self.long_rest_start.as_ref(),
+            Cue::Complete => self.complete.as_ref(),
+            Cue::Warning => self.warning.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, cue: Cue, sound: Option<CueSound>) {
+        match cue {
+            Cue::WorkStart => self.work_start = sound,
+            Cue::RestStart => self.rest_start = sound,
+            Cue::LongRestStart => self.long_rest_start = sound,
+            Cue::Complete => self.complete = sound,
+            Cue::Warning => self.warning = sound,
+        }
+    }
+
+    /// These sounds, with anything they leave out taken from `fallback`.
+    pub fn or(&self, fallback: &SoundSet) -> SoundSet {
+        SoundSet {
+            work_start: self.work_start.clone().or_else(|| fallback.work_start.clone()),
+            rest_start: self.rest_start.clone().or_else(|| fallback.rest_start.clone()),
+            long_rest_start: self.long_rest_start.clone().or_else(|| fallback.long_rest_start.clone()),
+            complete: self.complete.clone().or_else(|| fallback.complete.clone()),
+            warning: self.warning.clone().or_else(|| fallback.warning.clone()),
+            volume: self.volume.or(fallback.volume),
+            repeat: self.repeat.or(fallback.repeat),
+        }
+    }
+
+    /// The file to play for `cue`, if there is one.
+    pub fn path(&self, cue: Cue) -> Option<&Path> {
+        match self.get(cue) {
+            Some(CueSound::File(path)) => Some(Path::new(path)),
+            Some(CueSound::Silent) | None => None,
+        }
+    }
+
+    pub fn playback(&self) -> Playback {
+        let default = Playback::default();
+
+        Playback {
+            volume: self.volume.map_or(default.volume, |volume| volume.min(100)),
+            repeat: self.repeat.map_or(default.repeat, |repeat| repeat.clamp(1, MAX_REPEAT)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::app_settings::AppSettings;
+
+    #[test]
+    fn schedule_sounds_should_override_the_apps_and_fall_back_on_the_old_sound_path() {
+        let settings = AppSettings {
+            sound_path: Some(String::from("/sounds/bell.mp3")),
+            sounds: SoundSet {
+                rest_start: Some(CueSound::File(String::from("/sounds/rest.ogg"))),
+                complete: Some(CueSound::Silent),
+                volume: Some(60),
+                ..SoundSet::default()
+            },
+            ..AppSettings::default()
+        };
+
+        let schedule = SoundSet {
+            work_start: Some(CueSound::File(String::from("/sounds/gong.wav"))),
+            repeat: Some(3),
+            ..SoundSet::default()
+        };
+
+        let sounds = settings.sounds_for(&schedule);
+
+        assert_eq!(sounds.path(Cue::WorkStart), Some(Path::new("/sounds/gong.wav")));
+        assert_eq!(sounds.path(Cue::RestStart), Some(Path::new("/sounds/rest.ogg")));
+        assert_eq!(sounds.path(Cue::LongRestStart), Some(Path::new("/sounds/bell.mp3")));
+        assert_eq!(sounds.path(Cue::Complete), None);
+        assert_eq!(sounds.get(Cue::Warning), None, "warnings shouldn't sound like transitions");
+        assert_eq!(sounds.playback(), Playback { volume: 60, repeat: 3 });
+
+        assert_eq!(AppSettings::default().sounds_for(&SoundSet::default()).playback(), Playback::default());
+        assert!(SoundSet { repeat: Some(500), ..SoundSet::default() }.playback().repeat == MAX_REPEAT);
+    }
+}
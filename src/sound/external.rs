@@ -0,0 +1,97 @@
+use std::{io, path::{Path, PathBuf}, process::{Child, Command, Stdio}, sync::mpsc::Sender, thread};
+
+use super::{AudioBackend, Playback, SoundError};
+
+/// Plays sounds by running another program, like `paplay`, with the sound's path added to the end of its command.
+pub struct ExternalPlayer {
+    program: String,
+    args: Vec<String>,
+    errors: Sender<SoundError>,
+}
+
+impl ExternalPlayer {
+    /// `command` is split on whitespace. Failures that happen once the player has started are sent to `errors`.
+    pub fn new(command: &str, errors: Sender<SoundError>) -> ExternalPlayer {
+        let mut words = command.split_whitespace().map(String::from);
+
+        ExternalPlayer {
+            program: words.next().unwrap_or_default(),
+            args: words.collect(),
+            errors,
+        }
+    }
+}
+
+/// The arguments that set the volume of the players that are known to have a way to, which go before the rest.
+/// Anything else plays at its own volume.
+fn volume_args(program: &str, volume: u8) -> Vec<String> {
+    let volume = u32::from(volume);
+
+    match Path::new(program).file_name().and_then(|name| name.to_str()) {
+        Some("paplay") => vec![format!("--volume={}", 65536 * volume / 100)],
+        Some("ffplay") => vec![String::from("-volume"), volume.to_string()],
+        Some("mpg123") => vec![String::from("-f"), (32768 * volume / 100).to_string()],
+        _ => Vec::new(),
+    }
+}
+
+fn spawn(program: &str, args: &[String], path: &Path) -> io::Result<Child> {
+    Command::new(program)
+        .args(args)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+impl AudioBackend for ExternalPlayer {
+    fn play(&mut self, path: &Path, playback: Playback) -> Result<(), SoundError> {
+        let unavailable = |reason: String| SoundError::PlayerUnavailable { player: self.program.clone(), reason };
+
+        if self.program.is_empty() {
+            return Err(unavailable(String::from("no command was given")));
+        }
+
+        let mut args = if playback.volume == Playback::default().volume {
+            Vec::new()
+        } else {
+            volume_args(&self.program, playback.volume)
+        };
+        args.extend(self.args.iter().cloned());
+
+        let mut child = spawn(&self.program, &args, path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => unavailable(String::from("it isn't installed")),
+            _ => unavailable(e.to_string()),
+        })?;
+
+        //The sound carries on while the timer does, so the player is waited on separately, and any failure reported from there
+        let program = self.program.clone();
+        let path = PathBuf::from(path);
+        let errors = self.errors.clone();
+
+        thread::spawn(move || {
+            let mut played = 1;
+
+            let reason = loop {
+                match child.wait() {
+                    Ok(status) if !status.success() => break format!("it exited with {status}"),
+                    Err(e) => break e.to_string(),
+                    Ok(_) if played >= playback.repeat => return,
+                    Ok(_) => {}
+                }
+
+                child = match spawn(&program, &args, &path) {
+                    Ok(next) => next,
+                    Err(e) => break e.to_string(),
+                };
+                played += 1;
+            };
+
+            //Nobody's listening any more if the schedule has already ended
+            errors.send(SoundError::PlayerFailed { player: program, reason }).ok();
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+pub mod cues;
+pub mod external;
+#[cfg(feature = "native-audio")]
+pub mod native;
+
+use std::{cell::RefCell, error::Error, fmt::Display, io, path::{Path, PathBuf}, rc::Rc, sync::mpsc::{self, Receiver, Sender}};
+
+use external::ExternalPlayer;
+
+/// Tried in order when the native backend isn't built in or can't play a sound. The sound's path is added to the end.
+pub const DEFAULT_PLAYERS: [&str; 4] = [
+    "paplay",
+    "aplay -q",
+    "ffplay -nodisp -autoexit -loglevel error",
+    "mpg123 -q",
+];
+
+/// Splits a comma separated list of player commands, or None if there aren't any.
+pub fn parse_players(list: &str) -> Option<Vec<String>> {
+    let players: Vec<String> = list.split(',')
+        .map(|player| player.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|player| !player.is_empty())
+        .collect();
+
+    if players.is_empty() {None} else {Some(players)}
+}
+
+/// How loud a sound should be and how many times to play it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Playback {
+    /// A percentage, from 0 to 100.
+    pub volume: u8,
+    pub repeat: u32,
+}
+
+impl Default for Playback {
+    fn default() -> Playback {
+        Playback { volume: 100, repeat: 1 }
+    }
+}
+
+#[derive(Debug)]
+pub enum SoundError {
+    NotFound(PathBuf),
+    /// The file is there, but couldn't be opened, like when it can't be read by this user.
+    Open {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The file isn't a sound the backend understands.
+    Decode {
+        path: PathBuf,
+        reason: String,
+    },
+    /// There's nowhere for the sound to be played, like no audio device.
+    Output(String),
+    /// An external player isn't installed or couldn't be run.
+    PlayerUnavailable {
+        player: String,
+        reason: String,
+    },
+    /// An external player ran, but gave up. These are reported after `play` has returned, through `SoundPlayer::take_errors`.
+    PlayerFailed {
+        player: String,
+        reason: String,
+    },
+    /// Every backend was tried, and these are why each of them couldn't play the sound.
+    AllFailed(Vec<SoundError>),
+}
+
+impl Display for SoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoundError::NotFound(path) => write!(f, "{} does not exist", path.display()),
+            SoundError::Open { path, source } => write!(f, "{} could not be opened: {source}", path.display()),
+            SoundError::Decode { path, reason } => write!(f, "{} could not be read as a sound: {reason}", path.display()),
+            SoundError::Output(reason) => write!(f, "no audio output: {reason}"),
+            SoundError::PlayerUnavailable { player, reason } => write!(f, "{player} could not be run: {reason}"),
+            SoundError::PlayerFailed { player, reason } => write!(f, "{player} failed: {reason}"),
+            SoundError::AllFailed(errors) => {
+                let reasons: Vec<String> = errors.iter().map(SoundError::to_string).collect();
+                write!(f, "the sound could not be played ({})", reasons.join("; "))
+            }
+        }
+    }
+}
+
+impl Error for SoundError {}
+
+/// Somewhere sounds can be played. Playing shouldn't block: the sound carries on in the background, repeats included.
+pub trait AudioBackend {
+    fn play(&mut self, path: &Path, playback: Playback) -> Result<(), SoundError>;
+}
+
+/// Plays nothing, only remembering what it was asked to play. For running without any audio, like in tests.
+#[derive(Default)]
+pub struct NullSink {
+    played: Rc<RefCell<Vec<(PathBuf, Playback)>>>,
+}
+
+impl NullSink {
+    /// Stays readable after the sink has been handed over to a `SoundPlayer`.
+    pub fn played(&self) -> Rc<RefCell<Vec<(PathBuf, Playback)>>> {
+        Rc::clone(&self.played)
+    }
+}
+
+impl AudioBackend for NullSink {
+    fn play(&mut self, path: &Path, playback: Playback) -> Result<(), SoundError> {
+        self.played.borrow_mut().push((path.to_path_buf(), playback));
+        Ok(())
+    }
+}
+
+/// Plays sounds through the first backend that manages to, starting with the native one when it's built in.
+pub struct SoundPlayer {
+    backends: Vec<Box<dyn AudioBackend>>,
+    errors: Receiver<SoundError>,
+    //Handed to external players, which only find out they've failed after `play` returns
+    error_sender: Sender<SoundError>,
+}
+
+impl SoundPlayer {
+    /// `players` are external player commands to fall back on, in order.
+    pub fn new(players: &[String]) -> SoundPlayer {
+        let mut player = SoundPlayer::with_backends(Vec::new());
+
+        #[cfg(feature = "native-audio")]
+        player.backends.push(Box::new(native::NativeOutput::default()));
+
+        for command in players {
+            player.backends.push(Box::new(ExternalPlayer::new(command, player.error_sender.clone())));
+        }
+
+        player
+    }
+
+    pub fn with_backends(backends: Vec<Box<dyn AudioBackend>>) -> SoundPlayer {
+        let (error_sender, errors) = mpsc::channel();
+
+        SoundPlayer { backends, errors, error_sender }
+    }
+
+    pub fn play(&mut self, path: &Path, playback: Playback) -> Result<(), SoundError> {
+        if !path.is_file() {
+            return Err(SoundError::NotFound(path.to_path_buf()));
+        }
+
+        let mut failures = Vec::new();
+
+        for backend in &mut self.backends {
+            match backend.play(path, playback) {
+                Ok(()) => return Ok(()),
+                Err(e) => failures.push(e),
+            }
+        }
+
+        Err(SoundError::AllFailed(failures))
+    }
+
+    /// Problems with sounds that were already playing when they happened.
+    pub fn take_errors(&self) -> Vec<SoundError> {
+        self.errors.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::{Duration, Instant}};
+
+    use super::*;
+
+    fn sound_file() -> PathBuf {
+        //Never actually decoded, so anything that exists will do
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")
+    }
+
+    #[test]
+    fn missing_players_should_fall_back_to_the_next_backend() {
+        let null = NullSink::default();
+        let played = null.played();
+
+        let (sender, _) = mpsc::channel();
+        let mut player = SoundPlayer::with_backends(vec![
+            Box::new(ExternalPlayer::new("automato-p-no-such-player --flag", sender)),
+            Box::new(null),
+        ]);
+
+        let playback = Playback { volume: 50, repeat: 2 };
+        player.play(&sound_file(), playback).unwrap();
+        assert_eq!(*played.borrow(), vec![(sound_file(), playback)]);
+
+        assert!(matches!(player.play(Path::new("/no/such/sound.wav"), playback), Err(SoundError::NotFound(_))));
+    }
+
+    #[test]
+    fn players_that_fail_should_be_reported_after_play_returns() {
+        let mut player = SoundPlayer::new(&[String::from("false"), String::from("automato-p-no-such-player")]);
+
+        player.play(&sound_file(), Playback::default()).expect("false can always be started");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut errors = player.take_errors();
+
+        while errors.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+            errors = player.take_errors();
+        }
+
+        assert!(matches!(errors.as_slice(), [SoundError::PlayerFailed { player, .. }] if player == "false"), "{errors:?}");
+
+        let Err(SoundError::AllFailed(errors)) = SoundPlayer::new(&[String::from("automato-p-no-such-player")]).play(&sound_file(), Playback::default()) else {
+            panic!("there's nothing that can play the sound");
+        };
+
+        assert!(matches!(errors.last(), Some(SoundError::PlayerUnavailable { .. })), "{errors:?}");
+    }
+}
@@ -0,0 +1,42 @@
+use std::{fs::File, io::{self, BufReader}, path::Path};
+
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+
+use super::{AudioBackend, Playback, SoundError};
+
+/// Decodes and plays sounds in-process on the default output device. Supports WAV, OGG Vorbis, FLAC and MP3.
+#[derive(Default)]
+pub struct NativeOutput {
+    /// Opened on the first sound, then kept for the rest of the run as sounds stop when it's dropped.
+    output: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl AudioBackend for NativeOutput {
+    fn play(&mut self, path: &Path, playback: Playback) -> Result<(), SoundError> {
+        let file = File::open(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => SoundError::NotFound(path.to_path_buf()),
+            _ => SoundError::Open { path: path.to_path_buf(), source: e },
+        })?;
+        let decoder = Decoder::new(BufReader::new(file))
+            .map_err(|e| SoundError::Decode { path: path.to_path_buf(), reason: e.to_string() })?;
+
+        if self.output.is_none() {
+            self.output = Some(OutputStream::try_default().map_err(|e| SoundError::Output(e.to_string()))?);
+        }
+
+        let (_, handle) = self.output.as_ref().expect("The output was just opened");
+        let sink = Sink::try_new(handle).map_err(|e| SoundError::Output(e.to_string()))?;
+
+        //Decoded once, then replayed from memory for every repeat
+        let sound = decoder.buffered();
+
+        for _ in 0..playback.repeat.max(1) {
+            sink.append(sound.clone());
+        }
+
+        sink.set_volume(f32::from(playback.volume) / 100.0);
+        sink.detach();
+
+        Ok(())
+    }
+}
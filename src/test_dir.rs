@@ -0,0 +1,29 @@
+use std::{fs, ops::Deref, path::{Path, PathBuf}, process};
+
+/// A fresh directory for a test to write real files in, which is removed again when it's dropped.
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// `name` has to be different for every test, as they're run at the same time.
+    pub fn new(name: &str) -> TestDir {
+        let path = std::env::temp_dir().join(format!("automato-p-{name}-{}", process::id()));
+        fs::remove_dir_all(&path).ok();
+        fs::create_dir_all(&path).unwrap();
+
+        TestDir(path)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}